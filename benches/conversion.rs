@@ -0,0 +1,58 @@
+//! Benchmarks for the [`Mesh`] geometry conversion.
+
+// The `criterion_group!` generates an undocumented public function.
+#![allow(missing_docs)]
+
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use bevy_trimesh::CachedTriMeshBuilder;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// The amount of verticies in the benchmark mesh, few enough to be
+/// addressed by the `u16` indicies.
+const VERTICIES: u32 = 60_000;
+
+/// The amount of triangles in the benchmark mesh.
+const TRIANGLES: u32 = 500_000;
+
+/// Build a mesh of the `[f32; 3]` positions, with the triangles cycling
+/// through the verticies, and with the given `indices`.
+fn triangle_mesh(indices: impl Fn(Vec<u32>) -> Indices) -> Mesh {
+    let positions: Vec<[f32; 3]> = (0..VERTICIES)
+        .map(|i| {
+            let i = i as f32;
+            [i, i * 0.5, i * 0.25]
+        })
+        .collect();
+    let indicies = (0..TRIANGLES * 3).map(|i| i % VERTICIES).collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(indices(indicies)));
+    mesh
+}
+
+/// Compare the generic iterator conversion with the fast path, through
+/// the same entry point: the `u32` indicies are in the trimesh format and
+/// take the fast path, while the `u16` copy of them is converted one by one.
+fn conversion(c: &mut Criterion) {
+    let fast = triangle_mesh(Indices::U32);
+    let generic = triangle_mesh(|indicies| {
+        Indices::U16(indicies.into_iter().map(|index| index as u16).collect())
+    });
+    let mut group = c.benchmark_group("conversion");
+
+    group.bench_function("generic", |b| {
+        b.iter(|| CachedTriMeshBuilder::from_mesh(black_box(&generic)).unwrap())
+    });
+    group.bench_function("fast_path", |b| {
+        b.iter(|| CachedTriMeshBuilder::from_mesh(black_box(&fast)).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, conversion);
+criterion_main!(benches);
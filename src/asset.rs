@@ -0,0 +1,114 @@
+//! Loading the precomputed [`TriMesh`] geometry as bevy assets.
+//!
+//! The `.trimesh` format is the [`CachedTriMeshBuilder`] serialized with
+//! `bincode`.
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::CachedTriMeshBuilder;
+#[cfg(doc)]
+use crate::TriMesh;
+
+/// The precomputed [`TriMesh`] geometry loaded from a `.trimesh` file.
+///
+/// Call [`CachedTriMeshBuilder::build`] on it to obtain the [`TriMesh`].
+#[derive(Debug, Deref, DerefMut, TypeUuid, Serialize, Deserialize)]
+#[uuid = "d3d904bd-755d-427f-8e2d-688c67d4613b"]
+pub struct TriMeshAsset(pub CachedTriMeshBuilder);
+
+impl TriMeshAsset {
+    /// Serialize the geometry in the `.trimesh` format.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    /// Deserialize the geometry from the `.trimesh` format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
+/// The [`AssetLoader`] for the [`TriMeshAsset`]s stored in `.trimesh` files.
+#[derive(Debug, Default)]
+pub struct TriMeshAssetLoader;
+
+impl AssetLoader for TriMeshAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let asset = TriMeshAsset::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["trimesh"]
+    }
+}
+
+/// Registers the [`TriMeshAsset`] and its [`TriMeshAssetLoader`].
+#[derive(Debug, Default)]
+pub struct TriMeshAssetPlugin;
+
+impl Plugin for TriMeshAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<TriMeshAsset>()
+            .init_asset_loader::<TriMeshAssetLoader>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::LoadState, prelude::shape};
+
+    use super::*;
+
+    #[test]
+    fn loads_the_written_geometry() {
+        let builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        let bytes = TriMeshAsset(builder).to_bytes().unwrap();
+        let folder = std::env::temp_dir().join(format!("bevy_trimesh_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("cube.trimesh"), &bytes).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin {
+                asset_folder: folder.to_string_lossy().into_owned(),
+                watch_for_changes: false,
+            })
+            .add_plugin(TriMeshAssetPlugin);
+        let handle: Handle<TriMeshAsset> = app.world.resource::<AssetServer>().load("cube.trimesh");
+        for _ in 0..1000 {
+            app.update();
+            if app
+                .world
+                .resource::<Assets<TriMeshAsset>>()
+                .contains(&handle)
+            {
+                break;
+            }
+            let state = app.world.resource::<AssetServer>().get_load_state(&handle);
+            assert_ne!(state, LoadState::Failed);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        let assets = app.world.resource::<Assets<TriMeshAsset>>();
+        let loaded = assets.get(&handle).expect("the asset is loaded");
+        assert_eq!(loaded.to_bytes().unwrap(), bytes);
+        let original = TriMeshAsset::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.as_parts(), original.as_parts());
+        assert_eq!(loaded.build().indices().len(), 12);
+    }
+}
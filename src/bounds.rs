@@ -0,0 +1,160 @@
+//! Bounding volumes of the [`Mesh`] geometry.
+
+use bevy::prelude::*;
+use parry3d::{
+    math::Real,
+    na::{Matrix3, Vector3},
+};
+
+use crate::{
+    collect_geometry, interop::point_to_vec3, prepare_trimesh_from_mesh, CachedTriMeshBuilder,
+    TriMeshBuildError,
+};
+
+/// Check that all the [`Mesh`] verticies lie within the box from `min` to
+/// `max`, inclusive, like for the physics backends with a limited
+/// coordinate range.
+///
+/// The first offending vertex is reported with
+/// [`TriMeshBuildError::OutOfBounds`]. The non-finite coordinates are
+/// always out of bounds.
+pub fn assert_within_bounds(mesh: &Mesh, min: Vec3, max: Vec3) -> Result<(), TriMeshBuildError> {
+    let (verticies, _) = prepare_trimesh_from_mesh(mesh)?;
+    for (index, vertex) in verticies.enumerate() {
+        let vertex = point_to_vec3(vertex).to_array();
+        let outside = (0..3).find(|&coord| !(min[coord]..=max[coord]).contains(&vertex[coord]));
+        if let Some(coord) = outside {
+            return Err(TriMeshBuildError::OutOfBounds { index, coord });
+        }
+    }
+    Ok(())
+}
+
+impl CachedTriMeshBuilder {
+    /// Move the verticies lying outside of the box from `min` to `max` onto
+    /// its surface.
+    ///
+    /// This is lossy, as the clamped triangles change their shape, and can
+    /// even become degenerate. The `NaN` coordinates are moved to `min`.
+    ///
+    /// Returns the amount of the clamped verticies.
+    pub fn clamp_to_bounds(&mut self, min: Vec3, max: Vec3) -> usize {
+        let mut clamped = 0;
+        for vertex in &mut self.verticies {
+            let before = *vertex;
+            for coord in 0..3 {
+                vertex[coord] = vertex[coord].max(min[coord]).min(max[coord]);
+            }
+            // Compare the bits, so that the `NaN`s count as clamped.
+            if before.coords.map(Real::to_bits) != vertex.coords.map(Real::to_bits) {
+                clamped += 1;
+            }
+        }
+        self.mark_changed();
+        clamped
+    }
+}
+
+/// Fit an oriented bounding box to the [`Mesh`] verticies, returning its
+/// center, orientation and half extents.
+///
+/// The box axes are the principal axes of the vertex cloud, that is
+/// the eigenvectors of its covariance matrix, and are ordered by
+/// the decreasing variance: the local `x` axis of the box is the one along
+/// which the verticies spread the most. The orientation is ambiguous for
+/// the verticies spread out evenly, like the ones of a cube.
+pub fn mesh_obb(mesh: &Mesh) -> Result<(Vec3, Quat, Vec3), TriMeshBuildError> {
+    let (verticies, _) = collect_geometry(mesh)?;
+    let count = verticies.len() as Real;
+    let mean = verticies
+        .iter()
+        .fold(Vector3::zeros(), |sum, vertex| sum + vertex.coords)
+        / count;
+    let covariance = verticies.iter().fold(Matrix3::zeros(), |sum, vertex| {
+        let offset = vertex.coords - mean;
+        sum + offset * offset.transpose()
+    }) / count;
+
+    let eigen = covariance.symmetric_eigen();
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+    let [x, y, _] = order.map(|axis| {
+        let axis = eigen.eigenvectors.column(axis);
+        Vec3::new(axis.x, axis.y, axis.z).normalize()
+    });
+    // Make the axes right-handed, so they form a rotation.
+    let axes = Mat3::from_cols(x, y, x.cross(y));
+
+    let (mins, maxs) = verticies.iter().fold(
+        (Vec3::splat(Real::MAX), Vec3::splat(Real::MIN)),
+        |(mins, maxs), &vertex| {
+            let local = axes.transpose() * point_to_vec3(vertex);
+            (mins.min(local), maxs.max(local))
+        },
+    );
+    let center = axes * ((mins + maxs) / 2.0);
+    let half_extents = (maxs - mins) / 2.0;
+    Ok((center, Quat::from_mat3(&axes), half_extents))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::mesh::VertexAttributeValues;
+
+    use super::*;
+
+    #[test]
+    fn fits_an_elongated_rotated_box() {
+        let transform = Transform::from_xyz(3.0, -1.0, 2.0).with_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            0.7,
+            0.4,
+            -0.2,
+        ));
+        let mut mesh = Mesh::from(shape::Box::new(8.0, 2.0, 1.0));
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        else {
+            unreachable!();
+        };
+        for position in positions {
+            *position = transform.transform_point(Vec3::from(*position)).to_array();
+        }
+
+        let (center, rotation, half_extents) = mesh_obb(&mesh).unwrap();
+        assert!(center.abs_diff_eq(transform.translation, 1e-4), "{center}");
+        assert!(
+            half_extents.abs_diff_eq(Vec3::new(4.0, 1.0, 0.5), 1e-4),
+            "{half_extents}"
+        );
+        // The axes match up to their signs.
+        for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            let dot = (rotation * axis).dot(transform.rotation * axis);
+            assert!((dot.abs() - 1.0).abs() < 1e-4, "{axis}: {dot}");
+        }
+    }
+
+    #[test]
+    fn reports_and_clamps_the_vertex_out_of_bounds() {
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        let (min, max) = (Vec3::splat(-1.0), Vec3::new(1.0, 0.5, 1.0));
+        assert_eq!(
+            assert_within_bounds(&cube, Vec3::splat(-1.0), Vec3::ONE),
+            Ok(())
+        );
+        let Err(TriMeshBuildError::OutOfBounds { index, coord }) =
+            assert_within_bounds(&cube, min, max)
+        else {
+            panic!("expected a vertex out of bounds");
+        };
+        assert_eq!(coord, 1);
+
+        let mut builder = CachedTriMeshBuilder::from_mesh(&cube).unwrap();
+        assert_eq!(builder.verticies[index].y, 1.0);
+        assert_eq!(builder.clamp_to_bounds(min, max), 12);
+        assert_eq!(builder.verticies[index].y, 0.5);
+        let aabb = *builder.build().local_aabb();
+        assert_eq!(point_to_vec3(aabb.mins), min);
+        assert_eq!(point_to_vec3(aabb.maxs), max);
+    }
+}
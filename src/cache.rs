@@ -0,0 +1,132 @@
+//! Building the [`TriMesh`]es on demand from within the systems.
+
+use bevy::{asset::HandleId, ecs::system::SystemParam, prelude::*, utils::HashMap};
+
+use crate::{trimesh_from_mesh, TriMesh, TriMeshBuildError};
+
+/// The [`TriMesh`]es built by the [`TriMeshes`], by the [`Mesh`] asset.
+///
+/// The entries are dropped when their [`Mesh`] assets are modified or removed.
+#[derive(Resource, Default)]
+pub struct TriMeshCache {
+    /// The built [`TriMesh`]es, or the errors building them.
+    trimeshes: HashMap<HandleId, Result<TriMesh, TriMeshBuildError>>,
+}
+
+impl TriMeshCache {
+    /// The amount of the cached [`TriMesh`]es and build errors.
+    pub fn len(&self) -> usize {
+        self.trimeshes.len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.trimeshes.is_empty()
+    }
+
+    /// Drop all the cached [`TriMesh`]es.
+    pub fn clear(&mut self) {
+        self.trimeshes.clear();
+    }
+}
+
+/// Access the [`TriMesh`]es of the [`Mesh`] assets, building them on
+/// the first access and caching them in the [`TriMeshCache`].
+#[derive(SystemParam)]
+pub struct TriMeshes<'w> {
+    /// The [`Mesh`] assets to build from.
+    meshes: Res<'w, Assets<Mesh>>,
+    /// The built [`TriMesh`]es.
+    cache: ResMut<'w, TriMeshCache>,
+}
+
+impl TriMeshes<'_> {
+    /// Get the [`TriMesh`] of the [`Mesh`], building it if it is not
+    /// cached yet.
+    ///
+    /// Returns [`None`] if the [`Mesh`] is not loaded, or if building
+    /// the [`TriMesh`] fails. See [`Self::try_get`] for the error.
+    pub fn get(&mut self, handle: &Handle<Mesh>) -> Option<&TriMesh> {
+        self.try_get(handle)?.ok()
+    }
+
+    /// Get the [`TriMesh`] of the [`Mesh`], or the error building it,
+    /// building it if it is not cached yet.
+    ///
+    /// Returns [`None`] if the [`Mesh`] is not loaded. The errors are cached
+    /// too, so the failing builds are not retried until the [`Mesh`] changes.
+    pub fn try_get(
+        &mut self,
+        handle: &Handle<Mesh>,
+    ) -> Option<Result<&TriMesh, &TriMeshBuildError>> {
+        let mesh = self.meshes.get(handle)?;
+        let trimesh = self
+            .cache
+            .trimeshes
+            .entry(handle.id())
+            .or_insert_with(|| trimesh_from_mesh(mesh));
+        Some(trimesh.as_ref())
+    }
+}
+
+/// Drop the [`TriMeshCache`] entries of the modified and the removed [`Mesh`]
+/// assets.
+pub fn invalidate_trimesh_cache(
+    mut events: EventReader<AssetEvent<Mesh>>,
+    mut cache: ResMut<TriMeshCache>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Modified { handle } | AssetEvent::Removed { handle } => {
+                cache.trimeshes.remove(&handle.id());
+            }
+            AssetEvent::Created { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{trimesh_empty, TriMeshPlugin};
+
+    /// The handle to look up, and the vertex counts of the looked up
+    /// [`TriMesh`]es.
+    #[derive(Resource)]
+    struct Lookups(Handle<Mesh>, Vec<usize>);
+
+    fn look_up(mut trimeshes: TriMeshes, mut lookups: ResMut<Lookups>) {
+        for _ in 0..2 {
+            let trimesh = trimeshes.get(&lookups.0).expect("the mesh is loaded");
+            let verticies = trimesh.vertices().len();
+            lookups.1.push(verticies);
+        }
+    }
+
+    #[test]
+    fn builds_each_trimesh_once() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_plugin(TriMeshPlugin)
+            .add_system(look_up);
+        let handle = app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Mesh::from(shape::Cube::new(1.0)));
+        app.insert_resource(Lookups(handle.clone(), Vec::new()));
+
+        app.update();
+        assert_eq!(app.world.resource::<TriMeshCache>().len(), 1);
+        // Swap in a stand-in, that a rebuild would replace.
+        app.world
+            .resource_mut::<TriMeshCache>()
+            .trimeshes
+            .insert(handle.id(), Ok(trimesh_empty()));
+        app.update();
+
+        assert_eq!(app.world.resource::<Lookups>().1, [24, 24, 1, 1]);
+        assert_eq!(app.world.resource::<TriMeshCache>().len(), 1);
+    }
+}
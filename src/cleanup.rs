@@ -0,0 +1,324 @@
+//! Cleaning up the cached [`TriMesh`] geometry.
+
+use bevy::utils::HashSet;
+use parry3d::math::Vector;
+
+use crate::CachedTriMeshBuilder;
+#[cfg(doc)]
+use crate::TriMesh;
+
+/// An error indicating the vertex order is not a permutation of
+/// the verticies.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum PermutationError {
+    /// The order has a different amount of verticies.
+    #[error("the order has {len} verticies, expected {expected}")]
+    WrongLength {
+        /// The amount of verticies in the order.
+        len: usize,
+        /// The amount of verticies to reorder.
+        expected: usize,
+    },
+    /// The order refers to a vertex that does not exist.
+    #[error("vertex {0} is out of bounds")]
+    OutOfBounds(u32),
+    /// The order lists a vertex more than once.
+    #[error("vertex {0} is listed more than once")]
+    Duplicate(u32),
+}
+
+impl CachedTriMeshBuilder {
+    /// Remove the verticies not referenced by any triangle, and update
+    /// the indicies accordingly.
+    ///
+    /// The remaining verticies keep their relative order.
+    pub fn prune_unused_vertices(&mut self) {
+        let mut used = vec![false; self.verticies.len()];
+        for &index in self.indicies.iter().flatten() {
+            used[index as usize] = true;
+        }
+
+        let mut next = 0;
+        let remap: Vec<u32> = used
+            .iter()
+            .map(|&used| {
+                let index = next;
+                next += u32::from(used);
+                index
+            })
+            .collect();
+
+        let mut used = used.into_iter();
+        self.verticies.retain(|_| used.next().unwrap_or_default());
+        for triangle in &mut self.indicies {
+            *triangle = triangle.map(|index| remap[index as usize]);
+        }
+        debug_assert_eq!(self.validate_invariant(), Ok(()));
+        self.mark_changed();
+    }
+
+    /// Remove the degenerate triangles, that is the ones that have a zero
+    /// area or use a vertex more than once.
+    ///
+    /// Returns the amount of the removed triangles.
+    pub fn remove_degenerate_triangles(&mut self) -> usize {
+        let before = self.indicies.len();
+        let verticies = std::mem::take(&mut self.verticies);
+        self.retain_triangles(|_, &[a, b, c]| {
+            if a == b || b == c || c == a {
+                return false;
+            }
+            let [a, b, c] = [a, b, c].map(|index| verticies[index as usize]);
+            (b - a).cross(&(c - a)) != Vector::zeros()
+        });
+        self.verticies = verticies;
+        self.mark_changed();
+        before - self.indicies.len()
+    }
+
+    /// Remove the triangles using the same verticies as an earlier triangle,
+    /// in any rotation.
+    ///
+    /// With `opposite_winding`, the triangles wound the other way, like
+    /// the back sides of the double-sided geometry, count as duplicates too.
+    /// The remaining triangles keep their relative order.
+    pub fn dedup_triangles(&mut self, opposite_winding: bool) {
+        // Only used for lookups, to keep the result deterministic.
+        let mut seen = HashSet::with_capacity(self.indicies.len());
+        self.retain_triangles(|_, &triangle| {
+            let mut key = triangle;
+            if opposite_winding {
+                key.sort_unstable();
+            } else {
+                let first = (0..3).min_by_key(|&corner| triangle[corner]).unwrap_or(0);
+                key.rotate_left(first);
+            }
+            seen.insert(key)
+        });
+        self.mark_changed();
+    }
+
+    /// Reorder the verticies so that the vertex at the position `i` is
+    /// the one previously at `new_order[i]`, and update the indicies
+    /// accordingly.
+    ///
+    /// The geometry is left untouched if `new_order` is not a permutation of
+    /// the verticies.
+    pub fn remap_vertices(&mut self, new_order: &[u32]) -> Result<(), PermutationError> {
+        let expected = self.verticies.len();
+        if new_order.len() != expected {
+            return Err(PermutationError::WrongLength {
+                len: new_order.len(),
+                expected,
+            });
+        }
+
+        let mut new_positions = vec![None; expected];
+        for (new_position, &old_position) in new_order.iter().enumerate() {
+            let slot = new_positions
+                .get_mut(old_position as usize)
+                .ok_or(PermutationError::OutOfBounds(old_position))?;
+            if slot.replace(new_position as u32).is_some() {
+                return Err(PermutationError::Duplicate(old_position));
+            }
+        }
+        // With the length matching and no duplicates, every slot is filled.
+        let new_positions: Vec<u32> = new_positions.into_iter().flatten().collect();
+
+        self.verticies = new_order
+            .iter()
+            .map(|&old_position| self.verticies[old_position as usize])
+            .collect();
+        for triangle in &mut self.indicies {
+            *triangle = triangle.map(|index| new_positions[index as usize]);
+        }
+        debug_assert_eq!(self.validate_invariant(), Ok(()));
+        self.mark_changed();
+        Ok(())
+    }
+
+    /// Shuffle the order of the tagged and, separately, of the untagged
+    /// triangles, deterministically for the given `seed`, like for testing
+    /// how the order affects the [`TriMesh`] BVH quality.
+    ///
+    /// The tagged triangles stay ahead of the untagged ones, as only
+    /// the leading triangles can be tagged; without any tags, all
+    /// the triangles are shuffled together. The triangles themselves are
+    /// left intact, and keep their tags and ids.
+    pub fn shuffle_triangles(&mut self, seed: u64) {
+        let mut state = seed;
+        let tagged = self.tags.len().min(self.indicies.len());
+        let mut order: Vec<usize> = (0..self.indicies.len()).collect();
+        let (tagged_order, untagged_order) = order.split_at_mut(tagged);
+        for part in [tagged_order, untagged_order] {
+            // The Fisher-Yates shuffle.
+            for i in (1..part.len()).rev() {
+                let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+                part.swap(i, j);
+            }
+        }
+
+        self.indicies = order
+            .iter()
+            .map(|&triangle| self.indicies[triangle])
+            .collect();
+        let tags: Vec<u32> = order[..tagged]
+            .iter()
+            .map(|&triangle| self.tags[triangle])
+            .collect();
+        self.tags[..tagged].copy_from_slice(&tags);
+        let ids = self.triangle_ids_mut();
+        let shuffled: Vec<u64> = order.iter().map(|&triangle| ids[triangle]).collect();
+        *ids = shuffled;
+        self.mark_changed();
+    }
+}
+
+/// Advance the SplitMix64 generator state and return the next random
+/// number.
+///
+/// Implemented here so that the shuffles stay the same across
+/// the dependency versions.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use parry3d::math::{Point, Real};
+
+    use super::*;
+
+    fn welded_cube() -> CachedTriMeshBuilder {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        builder.weld(1e-4);
+        builder
+    }
+
+    /// The corner positions of each triangle.
+    fn triangle_positions(builder: &CachedTriMeshBuilder) -> Vec<[Point<Real>; 3]> {
+        builder
+            .indicies
+            .iter()
+            .map(|triangle| triangle.map(|index| builder.verticies[index as usize]))
+            .collect()
+    }
+
+    /// Whether both builders have the same triangles, with the same
+    /// corner positions in the same corner order, regardless of the triangle
+    /// order and of the vertex indicies.
+    fn geometrically_equal(a: &CachedTriMeshBuilder, b: &CachedTriMeshBuilder) -> bool {
+        let sorted = |builder| {
+            let mut triangles: Vec<[[u32; 3]; 3]> = triangle_positions(builder)
+                .into_iter()
+                .map(|corners| corners.map(|corner| corner.coords.map(Real::to_bits).into()))
+                .collect();
+            triangles.sort_unstable();
+            triangles
+        };
+        sorted(a) == sorted(b)
+    }
+
+    #[test]
+    fn prunes_the_verticies_of_a_cut_off_corner() {
+        let mut builder = welded_cube();
+        assert_eq!(builder.verticies.len(), 8);
+        let corner = builder
+            .verticies
+            .iter()
+            .position(|&vertex| vertex == Point::new(1.0, 1.0, 1.0))
+            .unwrap() as u32;
+        builder
+            .indicies
+            .retain(|triangle| !triangle.contains(&corner));
+        let before = triangle_positions(&builder);
+
+        builder.prune_unused_vertices();
+        assert_eq!(builder.verticies.len(), 7);
+        assert!(!builder.verticies.contains(&Point::new(1.0, 1.0, 1.0)));
+        assert_eq!(triangle_positions(&builder), before);
+        assert_eq!(builder.validate_invariant(), Ok(()));
+    }
+
+    #[test]
+    fn remaps_the_verticies_in_reverse() {
+        let mut builder = welded_cube();
+        let before = triangle_positions(&builder);
+        let reversed: Vec<u32> = (0..8).rev().collect();
+        let original = builder.verticies.clone();
+
+        builder.remap_vertices(&reversed).unwrap();
+        let mut expected = original;
+        expected.reverse();
+        assert_eq!(builder.verticies, expected);
+        assert_eq!(triangle_positions(&builder), before);
+    }
+
+    #[test]
+    fn rejects_the_non_permutations() {
+        let mut builder = welded_cube();
+        let original = builder.as_parts().0.to_vec();
+        assert_eq!(
+            builder.remap_vertices(&[0, 1, 2]),
+            Err(PermutationError::WrongLength {
+                len: 3,
+                expected: 8
+            })
+        );
+        assert_eq!(
+            builder.remap_vertices(&[0, 1, 2, 3, 4, 5, 6, 6]),
+            Err(PermutationError::Duplicate(6))
+        );
+        assert_eq!(
+            builder.remap_vertices(&[0, 1, 2, 3, 4, 5, 6, 8]),
+            Err(PermutationError::OutOfBounds(8))
+        );
+        assert_eq!(builder.verticies, original);
+    }
+
+    #[test]
+    fn dedups_the_rotated_and_optionally_the_flipped_triangles() {
+        let mut builder = CachedTriMeshBuilder::new();
+        builder.verticies = vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(0.0, 0.0, 1.0),
+        ];
+        let triangles = vec![[0, 1, 2], [1, 2, 0], [0, 2, 1], [0, 1, 3], [3, 0, 1]];
+
+        builder.indicies = triangles.clone();
+        builder.dedup_triangles(false);
+        assert_eq!(builder.indicies, [[0, 1, 2], [0, 2, 1], [0, 1, 3]]);
+
+        builder.indicies = triangles;
+        builder.dedup_triangles(true);
+        assert_eq!(builder.indicies, [[0, 1, 2], [0, 1, 3]]);
+    }
+
+    #[test]
+    fn shuffles_the_triangles_deterministically() {
+        let sphere = Mesh::from(shape::UVSphere::default());
+        let original = CachedTriMeshBuilder::from_mesh(&sphere).unwrap();
+
+        let mut shuffled = CachedTriMeshBuilder::from_mesh(&sphere).unwrap();
+        shuffled.shuffle_triangles(7);
+        assert_ne!(shuffled.indicies, original.indicies);
+        assert!(geometrically_equal(&shuffled, &original));
+        let mut flipped = CachedTriMeshBuilder::from_mesh(&sphere).unwrap();
+        flipped.indicies[0].swap(1, 2);
+        assert!(!geometrically_equal(&flipped, &original));
+
+        let mut again = CachedTriMeshBuilder::from_mesh(&sphere).unwrap();
+        again.shuffle_triangles(7);
+        assert_eq!(again.indicies, shuffled.indicies);
+        let mut other = CachedTriMeshBuilder::from_mesh(&sphere).unwrap();
+        other.shuffle_triangles(8);
+        assert_ne!(other.indicies, shuffled.indicies);
+    }
+}
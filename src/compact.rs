@@ -0,0 +1,124 @@
+//! The [`TriMesh`] geometry cache with compactly stored indicies.
+
+use std::num::TryFromIntError;
+
+use bevy::prelude::*;
+use parry3d::math::{Point, Real};
+
+use crate::{new_trimesh, CachedTriMeshBuilder, TriMesh, TriMeshBuildError};
+
+/// The triangle indicies stored with the narrowest type that can address
+/// all the verticies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompactIndicies {
+    /// Used when there are at most `65536` verticies.
+    U16(Vec<[u16; 3]>),
+    /// Used for everything else.
+    U32(Vec<[u32; 3]>),
+}
+
+impl CompactIndicies {
+    /// Narrow the indicies down if the vertex count permits.
+    pub fn new(indicies: Vec<[u32; 3]>, vertex_count: usize) -> Self {
+        if vertex_count <= usize::from(u16::MAX) + 1 {
+            // Out of bounds indicies might still not fit, those are kept as is.
+            if let Ok(narrow) = indicies.iter().copied().map(narrow_triangle).collect() {
+                return Self::U16(narrow);
+            }
+        }
+        Self::U32(indicies)
+    }
+
+    /// Widen the indicies to the format that trimesh ingests.
+    pub fn to_u32(&self) -> Vec<[u32; 3]> {
+        match self {
+            Self::U16(indicies) => indicies
+                .iter()
+                .map(|triangle| triangle.map(u32::from))
+                .collect(),
+            Self::U32(indicies) => indicies.clone(),
+        }
+    }
+}
+
+/// Narrow the triangle indicies to `u16`.
+fn narrow_triangle([a, b, c]: [u32; 3]) -> Result<[u16; 3], TryFromIntError> {
+    Ok([a.try_into()?, b.try_into()?, c.try_into()?])
+}
+
+/// Holds the [`TriMesh`] geometry like the [`CachedTriMeshBuilder`], but
+/// stores the indicies as `u16` when the vertex count permits it.
+///
+/// Only the geometry is kept: the triangle tags and ids of
+/// the [`CachedTriMeshBuilder`] are dropped on the conversion.
+#[derive(Debug)]
+pub struct CompactTriMeshBuilder {
+    /// Precomputed verticies to use when constructing a [`TriMesh`].
+    pub verticies: Vec<Point<Real>>,
+    /// Precomputed indicies to use when constructing a [`TriMesh`].
+    pub indicies: CompactIndicies,
+}
+
+impl CompactTriMeshBuilder {
+    /// Extract the geometry from a [`Mesh`] and create
+    /// a [`CompactTriMeshBuilder`].
+    pub fn from_mesh(mesh: &Mesh) -> Result<Self, TriMeshBuildError> {
+        CachedTriMeshBuilder::from_mesh(mesh).map(Self::from)
+    }
+
+    /// Build a new [`TriMesh`] from the precomputed geometry.
+    ///
+    /// To be used multiple times to leverage the cached data.
+    pub fn build(&self) -> TriMesh {
+        new_trimesh(self.verticies.clone(), self.indicies.to_u32())
+    }
+}
+
+/// Keeps the verticies and narrows the indicies, dropping the triangle tags
+/// and ids.
+impl From<CachedTriMeshBuilder> for CompactTriMeshBuilder {
+    fn from(builder: CachedTriMeshBuilder) -> Self {
+        let CachedTriMeshBuilder {
+            verticies,
+            indicies,
+            ..
+        } = builder;
+        let indicies = CompactIndicies::new(indicies, verticies.len());
+        Self {
+            verticies,
+            indicies,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::shape;
+
+    use super::*;
+    use crate::trimesh_from_mesh;
+
+    #[test]
+    fn small_meshes_use_u16_and_build_the_same() {
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        let compact = CompactTriMeshBuilder::from_mesh(&cube).unwrap();
+        let CompactIndicies::U16(narrow) = &compact.indicies else {
+            panic!("expected the u16 indicies, got {:?}", compact.indicies);
+        };
+        assert_eq!(narrow.len(), 12);
+
+        let trimesh = compact.build();
+        let expected = trimesh_from_mesh(&cube).unwrap();
+        assert_eq!(trimesh.vertices(), expected.vertices());
+        assert_eq!(trimesh.indices(), expected.indices());
+    }
+
+    #[test]
+    fn out_of_range_indicies_stay_u32() {
+        let indicies = vec![[0, 1, 70_000]];
+        assert_eq!(
+            CompactIndicies::new(indicies.clone(), 3),
+            CompactIndicies::U32(indicies)
+        );
+    }
+}
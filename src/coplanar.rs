@@ -0,0 +1,290 @@
+//! Merging the coplanar triangles of the cached [`TriMesh`] geometry.
+
+use bevy::utils::HashMap;
+use parry3d::math::{Point, Real, Vector};
+
+#[cfg(doc)]
+use crate::TriMesh;
+use crate::{
+    geometry::triangle_normal,
+    topology::{edge_key, edge_map, triangle_edges},
+    CachedTriMeshBuilder,
+};
+
+/// A point projected onto the plane of a region.
+type PlanePoint = [Real; 2];
+
+/// The z component of the cross product of the 2D vectors.
+fn cross(a: PlanePoint, b: PlanePoint) -> Real {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+/// The 2D vector from `a` to `b`.
+fn sub(b: PlanePoint, a: PlanePoint) -> PlanePoint {
+    [b[0] - a[0], b[1] - a[1]]
+}
+
+/// Whether the point is inside or on the edges of the counter-clockwise
+/// triangle.
+fn in_triangle(point: PlanePoint, [a, b, c]: [PlanePoint; 3]) -> bool {
+    cross(sub(b, a), sub(point, a)) >= 0.0
+        && cross(sub(c, b), sub(point, b)) >= 0.0
+        && cross(sub(a, c), sub(point, c)) >= 0.0
+}
+
+/// Triangulate the counter-clockwise simple polygon by ear clipping.
+///
+/// Returns `None` if no ear can be found, which happens for
+/// the self-intersecting polygons.
+fn ear_clip(polygon: &[(u32, PlanePoint)]) -> Option<Vec<[u32; 3]>> {
+    let mut remaining = polygon.to_vec();
+    let mut triangles = Vec::with_capacity(polygon.len().saturating_sub(2));
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let ear = (0..len).find(|&i| {
+            let [prev, this, next] = [(i + len - 1) % len, i, (i + 1) % len];
+            let corners = [remaining[prev].1, remaining[this].1, remaining[next].1];
+            if cross(sub(corners[1], corners[0]), sub(corners[2], corners[1])) <= 0.0 {
+                return false;
+            }
+            remaining
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != prev && j != this && j != next)
+                .all(|(_, &(_, point))| !in_triangle(point, corners))
+        })?;
+        let [prev, next] = [(ear + len - 1) % len, (ear + 1) % len];
+        triangles.push([remaining[prev].0, remaining[ear].0, remaining[next].0]);
+        remaining.remove(ear);
+    }
+    let [a, b, c] = [remaining[0], remaining[1], remaining[2]];
+    if cross(sub(b.1, a.1), sub(c.1, b.1)) <= 0.0 {
+        return None;
+    }
+    triangles.push([a.0, b.0, c.0]);
+    Some(triangles)
+}
+
+impl CachedTriMeshBuilder {
+    /// Merge the adjacent coplanar triangles, retriangulating each flat
+    /// region with as few triangles as its outline allows.
+    ///
+    /// Triangles are considered coplanar when the angle between their
+    /// normals is at most `angle_tolerance` radians, and adjacent when they
+    /// share the vertex indicies of an edge, so the meshes with
+    /// the duplicated verticies have to be welded first. Only triangles with
+    /// the same tag are merged. The verticies along straight edges of
+    /// the outline are dropped if no other triangle uses them, so
+    /// the neighbouring regions keep sharing their edges. Regions with holes
+    /// or a self-touching outline are left as is.
+    ///
+    /// The merged triangles get new ids, see
+    /// [`CachedTriMeshBuilder::triangles_with_ids`]. The verticies no longer
+    /// used by any triangle are kept, use
+    /// [`CachedTriMeshBuilder::prune_unused_vertices`] to remove them.
+    ///
+    /// Returns the amount of the removed triangles.
+    pub fn merge_coplanar(&mut self, angle_tolerance: Real) -> usize {
+        let before = self.indicies.len();
+        // Give the rounding errors of the normals some room.
+        let min_cos = angle_tolerance.cos().min(1.0 - 1e-6);
+        let edges = edge_map(&self.indicies);
+        let normals: Vec<Vector<Real>> = self
+            .indicies
+            .iter()
+            .map(|triangle| triangle_normal(triangle.map(|index| self.verticies[index as usize])))
+            .collect();
+        let mut uses = vec![0usize; self.verticies.len()];
+        for &index in self.indicies.iter().flatten() {
+            uses[index as usize] += 1;
+        }
+
+        let mut region_of = vec![usize::MAX; self.indicies.len()];
+        let mut indicies = Vec::with_capacity(self.indicies.len());
+        let mut tags = Vec::with_capacity(self.tags.len());
+        let old_ids = self.triangle_ids_mut().clone();
+        let mut ids = Vec::with_capacity(old_ids.len());
+        let mut region = Vec::new();
+
+        for seed in 0..self.indicies.len() {
+            if region_of[seed] != usize::MAX {
+                continue;
+            }
+            region_of[seed] = seed;
+            region.clear();
+            region.push(seed);
+            let normal = normals[seed];
+            let tag = self.tag_of(seed as u32);
+
+            // Degenerate triangles have no plane to merge along.
+            let mut next = 0;
+            while let Some(&triangle) = region.get(next).filter(|_| normal != Vector::zeros()) {
+                next += 1;
+                for (from, to) in triangle_edges(self.indicies[triangle]) {
+                    for &neighbour in &edges[&edge_key(from, to)] {
+                        if region_of[neighbour] == usize::MAX
+                            && normals[neighbour].dot(&normal) >= min_cos
+                            && self.tag_of(neighbour as u32) == tag
+                        {
+                            region_of[neighbour] = seed;
+                            region.push(neighbour);
+                        }
+                    }
+                }
+            }
+
+            let merged = self
+                .retriangulate(&region, &region_of, &edges, &uses, normal, min_cos)
+                .filter(|merged| merged.len() < region.len());
+            let region_len = match merged {
+                Some(merged) => {
+                    let len = merged.len();
+                    indicies.extend(merged);
+                    ids.extend((0..len).map(|_| self.new_triangle_id()));
+                    len
+                }
+                None => {
+                    indicies.extend(region.iter().map(|&triangle| self.indicies[triangle]));
+                    ids.extend(region.iter().map(|&triangle| old_ids[triangle]));
+                    region.len()
+                }
+            };
+            // The tagged triangles come first, and so do the regions seeded
+            // by them, so the tags stay a prefix of the triangles.
+            if let Some(tag) = tag {
+                tags.extend(std::iter::repeat_n(tag, region_len));
+            }
+        }
+
+        self.indicies = indicies;
+        self.tags = tags;
+        *self.triangle_ids_mut() = ids;
+        debug_assert_eq!(self.validate_invariant(), Ok(()));
+        self.mark_changed();
+        before - self.indicies.len()
+    }
+
+    /// Triangulate the outline of the flat region of triangles.
+    ///
+    /// Returns `None` if the region does not have a single simple outline.
+    fn retriangulate(
+        &self,
+        region: &[usize],
+        region_of: &[usize],
+        edges: &HashMap<[u32; 2], Vec<usize>>,
+        uses: &[usize],
+        normal: Vector<Real>,
+        min_cos: Real,
+    ) -> Option<Vec<[u32; 3]>> {
+        if region.len() < 2 {
+            return None;
+        }
+        let id = region_of[region[0]];
+
+        let mut outline = HashMap::default();
+        let mut region_uses: HashMap<u32, usize> = HashMap::default();
+        for &triangle in region {
+            for &index in &self.indicies[triangle] {
+                *region_uses.entry(index).or_default() += 1;
+            }
+            for (from, to) in triangle_edges(self.indicies[triangle]) {
+                let shared = edges[&edge_key(from, to)]
+                    .iter()
+                    .filter(|&&other| region_of[other] == id)
+                    .count();
+                if shared == 1 && outline.insert(from, to).is_some() {
+                    return None;
+                }
+            }
+        }
+
+        let (&start, _) = outline.iter().min_by_key(|&(&from, _)| from)?;
+        let mut loop_verticies = vec![start];
+        let mut current = outline[&start];
+        while current != start {
+            if loop_verticies.len() > outline.len() {
+                return None;
+            }
+            loop_verticies.push(current);
+            current = *outline.get(&current)?;
+        }
+        if loop_verticies.len() != outline.len() {
+            return None;
+        }
+
+        let u = normal.cross(&Vector::x());
+        let u = u
+            .try_normalize(1e-3)
+            .unwrap_or_else(|| normal.cross(&Vector::y()).normalize());
+        let v = normal.cross(&u);
+        let project = |index: u32| -> PlanePoint {
+            let point: Point<Real> = self.verticies[index as usize];
+            [point.coords.dot(&u), point.coords.dot(&v)]
+        };
+
+        // Drop the verticies along the straight edges of the outline that
+        // only this region uses.
+        let mut polygon: Vec<(u32, PlanePoint)> = loop_verticies
+            .iter()
+            .map(|&index| (index, project(index)))
+            .collect();
+        let mut changed = true;
+        while changed && polygon.len() > 3 {
+            changed = false;
+            let len = polygon.len();
+            for i in 0..len {
+                let (index, point) = polygon[i];
+                if region_uses[&index] != uses[index as usize] {
+                    continue;
+                }
+                let prev = polygon[(i + len - 1) % len].1;
+                let next = polygon[(i + 1) % len].1;
+                let [a, b] = [sub(point, prev), sub(next, point)];
+                let lengths = (a[0].hypot(a[1])) * (b[0].hypot(b[1]));
+                let dot = a[0] * b[0] + a[1] * b[1];
+                if lengths > 0.0 && dot >= min_cos * lengths {
+                    polygon.remove(i);
+                    changed = true;
+                    break;
+                }
+            }
+        }
+
+        ear_clip(&polygon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::TriMesh;
+
+    #[test]
+    fn merges_a_flat_grid() {
+        let grid = Mesh::from(shape::Plane {
+            size: 4.0,
+            subdivisions: 3,
+        });
+        let mut builder = CachedTriMeshBuilder::from_mesh(&grid).unwrap();
+        let before = builder.build();
+        let area = |trimesh: &TriMesh| -> Real { trimesh.triangles().map(|t| t.area()).sum() };
+
+        let removed = builder.merge_coplanar(1e-3);
+        let after = builder.build();
+        assert_eq!(removed, before.indices().len() - after.indices().len());
+        assert_eq!(after.indices().len(), 2);
+        assert_eq!(after.local_aabb(), before.local_aabb());
+        assert!((area(&after) - area(&before)).abs() < 1e-4);
+        assert!(triangle_normals_up(&builder));
+    }
+
+    /// Whether all the triangles still face up.
+    fn triangle_normals_up(builder: &CachedTriMeshBuilder) -> bool {
+        builder.indicies.iter().all(|triangle| {
+            let normal = triangle_normal(triangle.map(|index| builder.verticies[index as usize]));
+            (normal - Vector::y()).norm() < 1e-6
+        })
+    }
+}
@@ -0,0 +1,100 @@
+//! The [`Diagnostics`] of the [`TriMesh`] builds done by
+//! the [`TriMeshPlugin`].
+
+use std::time::Duration;
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+};
+
+#[cfg(doc)]
+use crate::{TriMesh, TriMeshPlugin};
+
+/// Registers the [`TriMesh`] build [`Diagnostics`], that are then measured
+/// by the [`TriMeshPlugin`].
+#[derive(Debug, Default)]
+pub struct TriMeshDiagnosticsPlugin;
+
+impl Plugin for TriMeshDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(Self::setup_system);
+    }
+}
+
+impl TriMeshDiagnosticsPlugin {
+    /// The time it took to build a [`TriMesh`], in milliseconds.
+    pub const BUILD_TIME: DiagnosticId =
+        DiagnosticId::from_u128(0x5b3c_9f3e_8a11_4d1c_a6a2_0d6e_41b5_7c01);
+    /// The amount of the [`TriMesh`]es built in a frame.
+    pub const BUILDS_PER_FRAME: DiagnosticId =
+        DiagnosticId::from_u128(0x5b3c_9f3e_8a11_4d1c_a6a2_0d6e_41b5_7c02);
+    /// The amount of triangles in a built [`TriMesh`].
+    pub const TRIANGLE_COUNT: DiagnosticId =
+        DiagnosticId::from_u128(0x5b3c_9f3e_8a11_4d1c_a6a2_0d6e_41b5_7c03);
+
+    /// Register the diagnostics.
+    pub fn setup_system(mut diagnostics: ResMut<Diagnostics>) {
+        diagnostics
+            .add(Diagnostic::new(Self::BUILD_TIME, "trimesh_build_time_ms", 20).with_suffix("ms"));
+        diagnostics.add(Diagnostic::new(
+            Self::BUILDS_PER_FRAME,
+            "trimesh_builds_per_frame",
+            20,
+        ));
+        diagnostics.add(Diagnostic::new(
+            Self::TRIANGLE_COUNT,
+            "trimesh_triangle_count",
+            20,
+        ));
+    }
+}
+
+/// Measure a single [`TriMesh`] build.
+pub(crate) fn measure_build(diagnostics: &mut Diagnostics, elapsed: Duration, triangles: usize) {
+    diagnostics.add_measurement(TriMeshDiagnosticsPlugin::BUILD_TIME, || {
+        elapsed.as_secs_f64() * 1000.0
+    });
+    diagnostics.add_measurement(TriMeshDiagnosticsPlugin::TRIANGLE_COUNT, || {
+        triangles as f64
+    });
+}
+
+/// Measure the amount of the [`TriMesh`] builds in a frame.
+pub(crate) fn measure_frame(diagnostics: &mut Diagnostics, builds: usize) {
+    diagnostics.add_measurement(TriMeshDiagnosticsPlugin::BUILDS_PER_FRAME, || builds as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TriMeshBuildQueue, TriMeshPlugin};
+
+    #[test]
+    fn measures_the_queued_builds() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .init_resource::<Diagnostics>()
+            .add_plugin(TriMeshPlugin)
+            .add_plugin(TriMeshDiagnosticsPlugin);
+        let mesh = app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Mesh::from(shape::Cube::new(1.0)));
+        let entity = app.world.spawn_empty().id();
+        app.world
+            .resource_mut::<TriMeshBuildQueue>()
+            .push(entity, mesh);
+
+        app.update();
+
+        let diagnostics = app.world.resource::<Diagnostics>();
+        let value = |id| diagnostics.get(id).expect("registered").value();
+        assert_eq!(value(TriMeshDiagnosticsPlugin::BUILDS_PER_FRAME), Some(1.0));
+        assert_eq!(value(TriMeshDiagnosticsPlugin::TRIANGLE_COUNT), Some(12.0));
+        let build_time = value(TriMeshDiagnosticsPlugin::BUILD_TIME).unwrap();
+        assert!(build_time >= 0.0);
+    }
+}
@@ -0,0 +1,84 @@
+//! Exporting the [`TriMesh`] for the inspection in the external tools.
+
+use std::io::{self, Write};
+
+use crate::{geometry::triangle_normal, TriMesh};
+
+/// Write the [`TriMesh`] in the Wavefront OBJ format.
+pub fn write_obj(trimesh: &TriMesh, mut writer: impl Write) -> io::Result<()> {
+    for vertex in trimesh.vertices() {
+        writeln!(writer, "v {} {} {}", vertex.x, vertex.y, vertex.z)?;
+    }
+    // The OBJ indicies are 1-based.
+    for [a, b, c] in trimesh.indices() {
+        writeln!(writer, "f {} {} {}", a + 1, b + 1, c + 1)?;
+    }
+    Ok(())
+}
+
+/// Write the [`TriMesh`] in the ASCII STL format.
+pub fn write_stl(trimesh: &TriMesh, mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "solid trimesh")?;
+    for triangle in trimesh.triangles() {
+        let normal = triangle_normal([triangle.a, triangle.b, triangle.c]);
+        writeln!(
+            writer,
+            "facet normal {} {} {}",
+            normal.x, normal.y, normal.z
+        )?;
+        writeln!(writer, "outer loop")?;
+        for vertex in triangle.vertices() {
+            writeln!(writer, "vertex {} {} {}", vertex.x, vertex.y, vertex.z)?;
+        }
+        writeln!(writer, "endloop")?;
+        writeln!(writer, "endfacet")?;
+    }
+    writeln!(writer, "endsolid trimesh")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use parry3d::math::Point;
+
+    use super::*;
+    use crate::trimesh_from_mesh;
+
+    #[test]
+    fn obj_reads_back_the_same_geometry() {
+        let trimesh = trimesh_from_mesh(&shape::Icosphere::default().try_into().unwrap()).unwrap();
+        let mut obj = Vec::new();
+        write_obj(&trimesh, &mut obj).unwrap();
+
+        let mut verticies = Vec::new();
+        let mut indicies = Vec::new();
+        for line in String::from_utf8(obj).unwrap().lines() {
+            let (kind, values) = line.split_once(' ').unwrap();
+            let values = values.split(' ');
+            match kind {
+                "v" => {
+                    let [x, y, z] = values
+                        .map(|value| value.parse().unwrap())
+                        .collect::<Vec<_>>()[..]
+                    else {
+                        panic!("expected three coordinates in {line:?}");
+                    };
+                    verticies.push(Point::new(x, y, z));
+                }
+                "f" => {
+                    let [a, b, c] = values
+                        .map(|value| value.parse::<u32>().unwrap() - 1)
+                        .collect::<Vec<_>>()[..]
+                    else {
+                        panic!("expected three indicies in {line:?}");
+                    };
+                    indicies.push([a, b, c]);
+                }
+                _ => panic!("unexpected line {line:?}"),
+            }
+        }
+        assert_eq!(&verticies, trimesh.vertices());
+        assert_eq!(&indicies, trimesh.indices());
+    }
+}
@@ -0,0 +1,170 @@
+//! Per-triangle geometry of a [`Mesh`].
+
+use bevy::prelude::*;
+use parry3d::math::{Point, Real, Vector};
+
+use crate::{
+    collect_geometry, interop::point_to_vec3, prepare_trimesh_from_mesh, topology::signed_volume,
+    TriMeshBuildError,
+};
+
+/// Which winding of the triangle verticies faces the front.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Winding {
+    /// The counter-clockwise triangles face the front, as in bevy.
+    #[default]
+    CounterClockwise,
+    /// The clockwise triangles face the front.
+    Clockwise,
+    /// Detect the winding from the sign of the volume enclosed by
+    /// the triangles, assuming they face outward.
+    ///
+    /// Only reliable for closed meshes, falls back to
+    /// [`Winding::CounterClockwise`] if the volume is zero.
+    Auto,
+}
+
+/// The unit normal of the triangle, following the counter-clockwise winding.
+///
+/// Degenerate triangles get the zero vector.
+pub(crate) fn triangle_normal([a, b, c]: [Point<Real>; 3]) -> Vector<Real> {
+    (b - a)
+        .cross(&(c - a))
+        .try_normalize(0.0)
+        .unwrap_or_else(Vector::zeros)
+}
+
+/// Iterate over the geometric normals of the [`Mesh`] triangles, in the same
+/// order as the triangles.
+///
+/// The normals point to the front side of the triangles, as determined by
+/// the `winding`. Degenerate triangles, that have no well-defined normal,
+/// yield a zero vector rather than `NaN`s.
+pub fn triangle_normals(
+    mesh: &Mesh,
+    winding: Winding,
+) -> Result<impl Iterator<Item = [f32; 3]> + '_, TriMeshBuildError> {
+    let (verticies, indicies) = prepare_trimesh_from_mesh(mesh)?;
+    let verticies: Vec<_> = verticies.collect();
+    let indicies: Vec<_> = indicies.collect();
+    let clockwise = match winding {
+        Winding::CounterClockwise => false,
+        Winding::Clockwise => true,
+        Winding::Auto => signed_volume(&verticies, &indicies) < 0.0,
+    };
+    let normals = indicies.into_iter().map(move |triangle| {
+        let normal = triangle_normal(triangle.map(|index| verticies[index as usize]));
+        if clockwise {
+            (-normal).into()
+        } else {
+            normal.into()
+        }
+    });
+    Ok(normals)
+}
+
+/// Compute the average of the [`Mesh`] vertex positions.
+pub fn vertex_centroid(mesh: &Mesh) -> Result<Vec3, TriMeshBuildError> {
+    let (verticies, _) = collect_geometry(mesh)?;
+    let sum: Vector<Real> = verticies.iter().map(|vertex| vertex.coords).sum();
+    Ok(point_to_vec3((sum / verticies.len() as Real).into()))
+}
+
+/// Compute the centroid of the [`Mesh`] surface, that is the average of
+/// the triangle centroids weighted by the triangle areas.
+///
+/// Unlike the [`vertex_centroid`], does not depend on how densely
+/// the surface is tessellated. Falls back to the [`vertex_centroid`] if
+/// the surface has no area.
+pub fn surface_centroid(mesh: &Mesh) -> Result<Vec3, TriMeshBuildError> {
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    let (weighted, area) = indicies.iter().fold(
+        (Vector::zeros(), 0.0),
+        |(weighted, area): (Vector<Real>, Real), triangle| {
+            let [a, b, c] = triangle.map(|index| verticies[index as usize]);
+            let triangle_area = (b - a).cross(&(c - a)).norm() / 2.0;
+            let centroid = (a.coords + b.coords + c.coords) / 3.0;
+            (weighted + centroid * triangle_area, area + triangle_area)
+        },
+    );
+    if area == 0.0 {
+        return vertex_centroid(mesh);
+    }
+    Ok(point_to_vec3((weighted / area).into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+
+    use super::*;
+
+    fn floor() -> Mesh {
+        Mesh::from(shape::Plane {
+            size: 4.0,
+            subdivisions: 2,
+        })
+    }
+
+    #[test]
+    fn floor_faces_up() {
+        let normals: Vec<_> = triangle_normals(&floor(), Winding::CounterClockwise)
+            .unwrap()
+            .collect();
+        assert_eq!(normals.len(), 18);
+        assert!(normals
+            .iter()
+            .all(|&normal| Vec3::from(normal).abs_diff_eq(Vec3::Y, 1e-6)));
+    }
+
+    #[test]
+    fn clockwise_winding_negates_the_normals() {
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        let normals =
+            |winding| -> Vec<[f32; 3]> { triangle_normals(&cube, winding).unwrap().collect() };
+        let counter_clockwise = normals(Winding::CounterClockwise);
+        let clockwise: Vec<_> = normals(Winding::Clockwise)
+            .into_iter()
+            .map(|normal| normal.map(|component| -component))
+            .collect();
+        assert_eq!(clockwise, counter_clockwise);
+        // The cube triangles face outward, so the volume is positive.
+        assert_eq!(normals(Winding::Auto), counter_clockwise);
+    }
+
+    #[test]
+    fn surface_centroid_is_weighted_by_the_area() {
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        assert!(vertex_centroid(&cube)
+            .unwrap()
+            .abs_diff_eq(Vec3::ZERO, 1e-6));
+        assert!(surface_centroid(&cube)
+            .unwrap()
+            .abs_diff_eq(Vec3::ZERO, 1e-6));
+
+        // A large triangle and a tiny one far away from it.
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 0.0],
+                [0.0, 0.0, 3.0],
+                [3.0, 0.0, 0.0],
+                [9.0, 0.0, 0.0],
+                [9.0, 0.0, 0.003],
+                [9.003, 0.0, 0.0],
+            ],
+        );
+        mesh.set_indices(Some(Indices::U32((0..6).collect())));
+        let vertex = vertex_centroid(&mesh).unwrap();
+        let surface = surface_centroid(&mesh).unwrap();
+        assert!(
+            vertex.abs_diff_eq(Vec3::new(5.0005, 0.0, 0.5005), 1e-4),
+            "{vertex}"
+        );
+        assert!(
+            surface.abs_diff_eq(Vec3::new(1.0, 0.0, 1.0), 1e-3),
+            "{surface}"
+        );
+    }
+}
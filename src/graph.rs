@@ -0,0 +1,75 @@
+//! Exporting the triangle connectivity as a [`petgraph`] graph.
+
+use bevy::prelude::*;
+use petgraph::graph::{NodeIndex, UnGraph};
+
+use crate::{
+    prepare_trimesh_from_mesh,
+    topology::{edge_key, edge_map, triangle_edges},
+    TriMeshBuildError,
+};
+
+/// Build the graph of the [`Mesh`] triangles, connecting the triangles that
+/// share an edge.
+///
+/// The node weights are the triangle indicies, and the nodes are added in
+/// the triangle order, so the node index matches the triangle index.
+/// Adjacency is determined by the shared vertex indicies, so meshes with
+/// the duplicated verticies have to be welded first. Each pair of
+/// the adjacent triangles is connected once, even if they share more than
+/// one edge.
+pub fn triangle_graph(mesh: &Mesh) -> Result<UnGraph<u32, ()>, TriMeshBuildError> {
+    let (_, indicies) = prepare_trimesh_from_mesh(mesh)?;
+    let indicies: Vec<_> = indicies.collect();
+    let edges = edge_map(&indicies);
+
+    let mut graph = UnGraph::with_capacity(indicies.len(), indicies.len() * 3 / 2);
+    for triangle in 0..indicies.len() {
+        graph.add_node(triangle as u32);
+    }
+    for (triangle, &triangle_indicies) in indicies.iter().enumerate() {
+        for (from, to) in triangle_edges(triangle_indicies) {
+            for &neighbour in &edges[&edge_key(from, to)] {
+                if neighbour > triangle {
+                    graph.update_edge(NodeIndex::new(triangle), NodeIndex::new(neighbour), ());
+                }
+            }
+        }
+    }
+    Ok(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+
+    use super::*;
+    use crate::CachedTriMeshBuilder;
+
+    #[test]
+    fn connects_the_triangles_of_a_cube() {
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        // Only the two triangles of each face share the verticies.
+        let graph = triangle_graph(&cube).unwrap();
+        assert_eq!((graph.node_count(), graph.edge_count()), (12, 6));
+
+        let mut builder = CachedTriMeshBuilder::from_mesh(&cube).unwrap();
+        builder.weld(1e-4);
+        let mut welded = Mesh::new(PrimitiveTopology::TriangleList);
+        let verticies: Vec<[f32; 3]> = builder
+            .verticies
+            .iter()
+            .map(|&vertex| vertex.into())
+            .collect();
+        welded.insert_attribute(Mesh::ATTRIBUTE_POSITION, verticies);
+        welded.set_indices(Some(Indices::U32(
+            builder.indicies.into_iter().flatten().collect(),
+        )));
+        // The 12 cube edges, and the 6 face diagonals.
+        let graph = triangle_graph(&welded).unwrap();
+        assert_eq!((graph.node_count(), graph.edge_count()), (12, 18));
+        assert!(graph
+            .node_indices()
+            .all(|node| graph.neighbors(node).count() == 3));
+    }
+}
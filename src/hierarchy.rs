@@ -0,0 +1,109 @@
+//! Building a single [`TriMesh`] for a whole entity hierarchy.
+
+use bevy::prelude::*;
+
+#[cfg(doc)]
+use crate::TriMesh;
+use crate::{CachedTriMeshBuilder, TriMeshComponent};
+
+/// Marks the root of an entity hierarchy, like a spawned scene, to build
+/// a single [`TriMesh`] for from the [`Mesh`]es of the root and all of its
+/// descendants.
+///
+/// The [`Mesh`]es are placed relative to the root, by accumulating
+/// the [`Transform`]s down the hierarchy, so the resulting
+/// [`TriMeshComponent`] is in the root space. The marker is removed once
+/// the [`TriMesh`] is built, or if building it fails.
+#[derive(Component, Debug, Default)]
+pub struct GenerateMergedTriMesh;
+
+/// Build the merged [`TriMesh`]es for the [`GenerateMergedTriMesh`] roots.
+///
+/// The roots that have no [`Mesh`]es in their hierarchy yet, like the scenes
+/// not spawned yet, or that have some of the [`Mesh`] assets not loaded yet,
+/// are retried on the later frames.
+pub fn build_merged_trimeshes(
+    mut commands: Commands,
+    roots: Query<Entity, With<GenerateMergedTriMesh>>,
+    children: Query<&Children>,
+    nodes: Query<(Option<&Transform>, Option<&Handle<Mesh>>)>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    for root in &roots {
+        // The descendants of the root, with their transforms relative to it.
+        let mut parts = Vec::new();
+        let mut stack = vec![(root, GlobalTransform::IDENTITY)];
+        while let Some((entity, relative)) = stack.pop() {
+            let Ok((_, handle)) = nodes.get(entity) else {
+                continue;
+            };
+            if let Some(handle) = handle {
+                parts.push((handle, relative));
+            }
+            for &child in children.get(entity).into_iter().flatten() {
+                let local = nodes
+                    .get(child)
+                    .ok()
+                    .and_then(|(transform, _)| transform.copied())
+                    .unwrap_or_default();
+                stack.push((child, relative.mul_transform(local)));
+            }
+        }
+
+        let parts: Option<Vec<_>> = parts
+            .into_iter()
+            .map(|(handle, relative)| Some((meshes.get(handle)?, relative)))
+            .collect();
+        let Some(parts) = parts.filter(|parts| !parts.is_empty()) else {
+            continue;
+        };
+
+        let mut commands = commands.entity(root);
+        commands.remove::<GenerateMergedTriMesh>();
+        match CachedTriMeshBuilder::from_meshes_with_global_transforms(parts) {
+            Ok(builder) => {
+                commands.insert(TriMeshComponent(builder.build()));
+            }
+            Err(error) => warn!("unable to build the merged trimesh for {root:?}: {error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parry3d::math::Point;
+
+    use super::*;
+    use crate::TriMeshPlugin;
+
+    #[test]
+    fn places_the_meshes_relative_to_the_root() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_plugin(TriMeshPlugin);
+        let cube = app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Mesh::from(shape::Cube::new(1.0)));
+        let root = app
+            .world
+            .spawn((GenerateMergedTriMesh, Transform::from_xyz(100.0, 0.0, 0.0)))
+            .with_children(|root| {
+                root.spawn(Transform::from_xyz(1.0, 0.0, 0.0).with_scale(Vec3::splat(2.0)))
+                    .with_children(|child| {
+                        child.spawn((Transform::from_xyz(0.0, 1.0, 0.0), cube));
+                    });
+            })
+            .id();
+
+        app.update();
+
+        let entity = app.world.entity(root);
+        assert!(!entity.contains::<GenerateMergedTriMesh>());
+        let aabb = entity.get::<TriMeshComponent>().unwrap().local_aabb();
+        assert_eq!(aabb.mins, Point::new(0.0, 1.0, -1.0));
+        assert_eq!(aabb.maxs, Point::new(2.0, 3.0, 1.0));
+    }
+}
@@ -0,0 +1,67 @@
+//! Comparing the [`Mesh`] to its convex hull.
+
+use bevy::prelude::*;
+use parry3d::transformation::try_convex_hull;
+
+use crate::{collect_geometry, topology::signed_volume, TriMeshBuildError};
+
+/// Compute the ratio of the volume enclosed by the [`Mesh`] to the volume of
+/// its convex hull.
+///
+/// Close to `1.0` for roughly convex meshes, that a convex hull collider
+/// approximates well, and lower for the ones that are better decomposed.
+/// Only meaningful for closed meshes. The flat meshes, and the ones the hull
+/// can not be computed for, get `0.0`.
+pub fn convexity_ratio(mesh: &Mesh) -> Result<f32, TriMeshBuildError> {
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    let Ok((hull_verticies, hull_indicies)) = try_convex_hull(&verticies) else {
+        return Ok(0.0);
+    };
+
+    let hull_volume = signed_volume(&hull_verticies, &hull_indicies).abs();
+    if hull_volume == 0.0 {
+        return Ok(0.0);
+    }
+    let volume = signed_volume(&verticies, &indicies).abs();
+    Ok(volume / hull_volume)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+
+    use super::*;
+    use crate::CachedTriMeshBuilder;
+
+    #[test]
+    fn cube_is_convex_and_l_shape_is_not() {
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        assert!((convexity_ratio(&cube).unwrap() - 1.0).abs() < 1e-5);
+
+        // Two boxes touching along a face, with the volumes of 2 and 1.
+        let builder = CachedTriMeshBuilder::from_meshes_with_transforms([
+            (
+                &shape::Box::new(2.0, 1.0, 1.0).into(),
+                Transform::from_xyz(1.0, 0.5, 0.5),
+            ),
+            (
+                &shape::Box::new(1.0, 1.0, 1.0).into(),
+                Transform::from_xyz(0.5, 1.5, 0.5),
+            ),
+        ])
+        .unwrap();
+        let mut l_shape = Mesh::new(PrimitiveTopology::TriangleList);
+        let verticies: Vec<[f32; 3]> = builder
+            .verticies
+            .iter()
+            .map(|&vertex| vertex.into())
+            .collect();
+        l_shape.insert_attribute(Mesh::ATTRIBUTE_POSITION, verticies);
+        l_shape.set_indices(Some(Indices::U32(
+            builder.indicies.into_iter().flatten().collect(),
+        )));
+        // The hull fills in the corner of the L, adding the volume of 0.5.
+        let ratio = convexity_ratio(&l_shape).unwrap();
+        assert!((ratio - 3.0 / 3.5).abs() < 1e-5, "{ratio}");
+    }
+}
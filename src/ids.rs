@@ -0,0 +1,111 @@
+//! Stable per-triangle ids of the cached [`TriMesh`] geometry.
+
+use crate::CachedTriMeshBuilder;
+#[cfg(doc)]
+use crate::TriMesh;
+
+/// The ids of the triangles of a [`CachedTriMeshBuilder`].
+///
+/// The ids are assigned lazily: the triangles past the end of `ids` get
+/// the consecutive ids starting at `next`, so the triangles appended
+/// directly to the [`CachedTriMeshBuilder::indicies`] get their ids too.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct TriangleIds {
+    /// The ids of the leading triangles.
+    ids: Vec<u64>,
+    /// The id of the first triangle past the end of `ids`.
+    next: u64,
+}
+
+impl TriangleIds {
+    /// Release the excess capacity of the ids buffer.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.ids.shrink_to_fit();
+    }
+
+    /// The bytes held by the ids buffer.
+    pub(crate) fn memory_footprint(&self) -> usize {
+        self.ids.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+impl CachedTriMeshBuilder {
+    /// Iterate over the triangles along with their ids.
+    ///
+    /// Each triangle gets a unique id when it is added, which it then keeps
+    /// through the welding, the pruning and the removal of the other
+    /// triangles, unlike its index. The ids are never reused, even after
+    /// [`CachedTriMeshBuilder::clear`].
+    pub fn triangles_with_ids(&self) -> impl Iterator<Item = (u64, [u32; 3])> + '_ {
+        let TriangleIds { ids, next } = &self.ids;
+        let assigned = ids.len().min(self.indicies.len());
+        ids[..assigned]
+            .iter()
+            .copied()
+            .chain(*next..)
+            .zip(self.indicies.iter().copied())
+    }
+
+    /// Assign the ids to all the triangles, so they can be reordered or
+    /// removed along with the triangles.
+    fn assign_triangle_ids(&mut self) {
+        let TriangleIds { ids, next } = &mut self.ids;
+        ids.truncate(self.indicies.len());
+        let missing = (self.indicies.len() - ids.len()) as u64;
+        ids.extend(*next..*next + missing);
+        *next += missing;
+    }
+
+    /// The mutable ids of the triangles, which are all assigned.
+    pub(crate) fn triangle_ids_mut(&mut self) -> &mut Vec<u64> {
+        self.assign_triangle_ids();
+        &mut self.ids.ids
+    }
+
+    /// Forget the ids of all the triangles, without reusing them.
+    pub(crate) fn clear_triangle_ids(&mut self) {
+        self.assign_triangle_ids();
+        self.ids.ids.clear();
+    }
+
+    /// Allocate a new id for a triangle replacing the removed ones.
+    pub(crate) fn new_triangle_id(&mut self) -> u64 {
+        self.assign_triangle_ids();
+        let id = self.ids.next;
+        self.ids.next += 1;
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn ids_survive_welding_pruning_and_removal() {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        let before: Vec<_> = builder.triangles_with_ids().map(|(id, _)| id).collect();
+        assert_eq!(before, (0..12).collect::<Vec<_>>());
+
+        builder.weld(1e-4);
+        builder.prune_unused_vertices();
+        let welded: Vec<_> = builder.triangles_with_ids().map(|(id, _)| id).collect();
+        assert_eq!(welded, before);
+
+        // Collapse the first triangle, so it is removed.
+        let [a, _, _] = builder.indicies[0];
+        builder.indicies[0] = [a, a, a];
+        builder.remove_degenerate_triangles();
+        let remaining: Vec<_> = builder.triangles_with_ids().map(|(id, _)| id).collect();
+        assert_eq!(remaining, (1..12).collect::<Vec<_>>());
+
+        builder.clear();
+        let region = builder.append_mesh(&shape::Cube::new(1.0).into()).unwrap();
+        assert_eq!(region.triangle_range, 0..12);
+        let appended: Vec<_> = builder.triangles_with_ids().map(|(id, _)| id).collect();
+        assert_eq!(appended, (12..24).collect::<Vec<_>>());
+    }
+}
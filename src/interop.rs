@@ -0,0 +1,87 @@
+//! Conversions between the `bevy` (`glam`) and the `parry3d` (`nalgebra`)
+//! math types.
+//!
+//! Both use the right-handed coordinates and the column-major matrices, so
+//! the components map over as is. The only difference is the quaternion
+//! component order: `nalgebra` constructs them as `(w, i, j, k)`,
+//! while `glam` uses `(x, y, z, w)`.
+
+use bevy::prelude::*;
+use parry3d::{
+    math::{Isometry, Point, Real, Translation, Vector},
+    na::{Quaternion, UnitQuaternion},
+};
+
+/// Convert a [`Point`] to a [`Vec3`].
+pub fn point_to_vec3(point: Point<Real>) -> Vec3 {
+    Vec3::new(point.x, point.y, point.z)
+}
+
+/// Convert a [`Vec3`] to a [`Point`].
+pub fn vec3_to_point(vec: Vec3) -> Point<Real> {
+    Point::new(vec.x, vec.y, vec.z)
+}
+
+/// Convert a [`Vec3`] to a [`Vector`].
+pub fn vec3_to_vector(vec: Vec3) -> Vector<Real> {
+    Vector::new(vec.x, vec.y, vec.z)
+}
+
+/// Convert the translation and the rotation of a [`Transform`] to
+/// an [`Isometry`].
+///
+/// An [`Isometry`] has no scale, so the [`Transform::scale`] is dropped.
+pub fn isometry_from_transform(transform: &Transform) -> Isometry<Real> {
+    let [x, y, z, w] = transform.rotation.to_array();
+    Isometry::from_parts(
+        Translation::from(vec3_to_vector(transform.translation)),
+        UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)),
+    )
+}
+
+/// Convert an [`Isometry`] to a [`Transform`] with the unit scale.
+pub fn transform_from_isometry(iso: &Isometry<Real>) -> Transform {
+    let rotation = iso.rotation.quaternion();
+    Transform {
+        translation: Vec3::from(<[Real; 3]>::from(iso.translation.vector)),
+        rotation: Quat::from_xyzw(rotation.i, rotation.j, rotation.k, rotation.w),
+        scale: Vec3::ONE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isometry_moves_the_points_like_the_transform() {
+        let transform = Transform::from_xyz(1.0, -2.0, 3.0).with_rotation(Quat::from_euler(
+            EulerRot::XYZ,
+            0.3,
+            -1.2,
+            2.0,
+        ));
+        let iso = isometry_from_transform(&transform);
+        let point = Vec3::new(0.5, 4.0, -7.0);
+        let moved = point_to_vec3(iso * vec3_to_point(point));
+        assert!(moved.abs_diff_eq(transform.transform_point(point), 1e-5));
+
+        let back = transform_from_isometry(&iso);
+        assert!(back.translation.abs_diff_eq(transform.translation, 1e-6));
+        assert!(back.rotation.abs_diff_eq(transform.rotation, 1e-6));
+        assert_eq!(back.scale, Vec3::ONE);
+    }
+
+    #[test]
+    fn quarter_turn_maps_the_known_axes() {
+        let transform =
+            Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+        let iso = isometry_from_transform(&transform);
+        let moved = iso * Point::new(1.0, 0.0, 0.0);
+        assert!((moved - Point::new(0.0, 1.0, 0.0)).norm() < 1e-6, "{moved}");
+        assert_eq!(
+            vec3_to_vector(Vec3::new(1.0, 2.0, 3.0)),
+            Vector::new(1.0, 2.0, 3.0)
+        );
+    }
+}
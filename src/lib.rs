@@ -1,12 +1,85 @@
 #![doc = include_str!("../README.md")]
-#![feature(array_chunks)]
+
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use bevy::{
     prelude::*,
-    render::mesh::{Indices, VertexAttributeValues},
+    render::{
+        mesh::{Indices, MeshVertexAttributeId, VertexAttributeValues},
+        render_resource::PrimitiveTopology,
+    },
+};
+use parry3d::math::{Isometry, Point, Real};
+pub use parry3d::{
+    self,
+    shape::{TopologyError, TriMesh, TriMeshFlags},
+};
+
+#[cfg(feature = "asset")]
+mod asset;
+mod bounds;
+mod cache;
+mod cleanup;
+mod compact;
+mod coplanar;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+#[cfg(feature = "export")]
+mod export;
+mod geometry;
+#[cfg(feature = "petgraph")]
+mod graph;
+mod hierarchy;
+mod hull;
+mod ids;
+mod interop;
+mod merge;
+mod plugin;
+mod preset;
+mod primitive;
+mod query;
+mod repair;
+mod skinning;
+mod tags;
+mod topology;
+mod validate;
+mod weld;
+
+#[cfg(feature = "asset")]
+pub use asset::{TriMeshAsset, TriMeshAssetLoader, TriMeshAssetPlugin};
+pub use bounds::{assert_within_bounds, mesh_obb};
+pub use cache::{invalidate_trimesh_cache, TriMeshCache, TriMeshes};
+pub use cleanup::PermutationError;
+pub use compact::{CompactIndicies, CompactTriMeshBuilder};
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::TriMeshDiagnosticsPlugin;
+#[cfg(feature = "export")]
+pub use export::{write_obj, write_stl};
+pub use geometry::{surface_centroid, triangle_normals, vertex_centroid, Winding};
+#[cfg(feature = "petgraph")]
+pub use graph::triangle_graph;
+pub use hierarchy::{build_merged_trimeshes, GenerateMergedTriMesh};
+pub use hull::convexity_ratio;
+pub use interop::{
+    isometry_from_transform, point_to_vec3, transform_from_isometry, vec3_to_point, vec3_to_vector,
 };
-use parry3d::math::{Point, Real};
-pub use parry3d::{self, shape::TriMesh};
+pub use merge::{MeshRegion, RegionError};
+pub use plugin::{
+    build_queued_trimeshes, SlowConversionReason, SlowTriMeshConversion, TriMeshBuildQueue,
+    TriMeshComponent, TriMeshPlugin,
+};
+pub use preset::TriMeshPreset;
+pub use primitive::detect_primitive;
+pub use query::{
+    meshes_intersect, pick, qbvh_from_mesh, resolve_feature, resolve_triangle_feature,
+    segment_blocked, segment_hit, signed_distance, MeshFeature, PickHit,
+};
+pub use repair::RepairReport;
+pub use skinning::trimesh_from_skinned;
+pub use topology::{
+    boundary_edges, euler_characteristic, genus, polyline_from_mesh, EdgeSelection,
+};
+pub use validate::{self_intersections, validate};
 
 /// The geometry extraction error.
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
@@ -15,8 +88,29 @@ pub enum ExtractGeometryError {
     #[error("no vertex position data found in the specified mesh")]
     NoVertexPositionData,
     /// Sometimes meshes come without indicies.
-    #[error("no vertex indicies found in the specified mesh")]
-    NoVertexIndicies,
+    ///
+    /// Carries the mesh topology to help deciding whether the indicies can
+    /// be synthesized.
+    #[error("no vertex indicies found in the specified mesh with {0:?} topology")]
+    NoVertexIndicies(PrimitiveTopology),
+    /// The mesh has no attributes and no indicies at all, as happens when
+    /// its data is not retained on the CPU, or was stripped from it.
+    ///
+    /// Keep the mesh data in the main world to convert it.
+    #[error("the mesh has no geometry data on the CPU, it has to be retained in the main world")]
+    GeometryNotCpuAvailable,
+}
+
+impl ExtractGeometryError {
+    /// The error for the [`Mesh`] missing the vertex positions, telling
+    /// apart the meshes that have no CPU data at all.
+    pub(crate) fn missing_positions(mesh: &Mesh) -> Self {
+        if mesh.attributes().next().is_none() && mesh.indices().is_none() {
+            Self::GeometryNotCpuAvailable
+        } else {
+            Self::NoVertexPositionData
+        }
+    }
 }
 
 /// Extract the geometry from a bevy [`Mesh`].
@@ -25,10 +119,12 @@ pub fn extract_geometry(
 ) -> Result<(&VertexAttributeValues, &Indices), ExtractGeometryError> {
     let verticies = mesh
         .attribute(Mesh::ATTRIBUTE_POSITION)
-        .ok_or(ExtractGeometryError::NoVertexPositionData)?;
+        .ok_or_else(|| ExtractGeometryError::missing_positions(mesh))?;
     let indicies = mesh
         .indices()
-        .ok_or(ExtractGeometryError::NoVertexIndicies)?;
+        .ok_or(ExtractGeometryError::NoVertexIndicies(
+            mesh.primitive_topology(),
+        ))?;
     Ok((verticies, indicies))
 }
 
@@ -38,25 +134,72 @@ pub fn extract_geometry(
 pub struct UnsupportedFormatError(&'static str);
 
 /// Convert vertices from the bevy format to a format that trimesh ingests.
+///
+/// The 2D [`VertexAttributeValues::Float32x2`] verticies are placed
+/// at `z = 0`, and the flat [`VertexAttributeValues::Float32`] values are
+/// grouped in the consecutive triples.
+///
+/// The [`Mesh::ATTRIBUTE_POSITION`] only accepts
+/// [`VertexAttributeValues::Float32x3`], so the 2D verticies and the flat
+/// values, like a custom [`VertexFormat::Float32`] collision attribute, are
+/// only reachable through [`prepare_trimesh_from_mesh_with_positions`] and
+/// [`prepare_trimesh_from_mesh_with_attribute`].
+///
+/// [`VertexFormat::Float32`]: bevy::render::render_resource::VertexFormat::Float32
 pub fn convert_verticies(
     verticies: &VertexAttributeValues,
 ) -> Result<impl Iterator<Item = Point<Real>> + '_, UnsupportedFormatError> {
-    let verticies = match verticies {
-        VertexAttributeValues::Float32x3(val) => val,
-        _ => return Err(UnsupportedFormatError("only [f32; 3] is supported")),
+    // Only one of these is non-empty, this avoids boxing the iterator.
+    let (verticies_3d, verticies_2d): (&[[f32; 3]], &[[f32; 2]]) = match verticies {
+        VertexAttributeValues::Float32x3(val) => (val, &[]),
+        VertexAttributeValues::Float32x2(val) => (&[], val),
+        VertexAttributeValues::Float32(val) => {
+            let val = bytemuck::try_cast_slice(val).map_err(|_| {
+                UnsupportedFormatError("the flat f32 length must be a multiple of 3")
+            })?;
+            (val, &[])
+        }
+        _ => {
+            return Err(UnsupportedFormatError(
+                "only [f32; 3], [f32; 2] and flat f32 are supported",
+            ))
+        }
     };
-    Ok(verticies.iter().map(|vertex| Point::from_slice(vertex)))
+    let verticies_3d = verticies_3d.iter().map(|vertex| Point::from_slice(vertex));
+    let verticies_2d = verticies_2d.iter().map(|&[x, y]| Point::new(x, y, 0.0));
+    Ok(verticies_3d.chain(verticies_2d))
 }
 
 /// Convert indicies from the bevy format to a format that trimesh ingests.
+///
+/// The [`Indices::U16`] are widened to `u32`.
 pub fn convert_indicies(
     indicies: &Indices,
 ) -> Result<impl Iterator<Item = [u32; 3]> + '_, UnsupportedFormatError> {
-    let indicies = match indicies {
-        Indices::U32(ref val) => val,
-        _ => return Err(UnsupportedFormatError("only u32 is supported")),
+    let (indicies_u32, indicies_u16): (&[u32], &[u16]) = match indicies {
+        Indices::U32(val) => (val, &[]),
+        Indices::U16(val) => (&[], val),
     };
-    Ok(indicies.array_chunks().copied())
+    Ok(convert_indicies_u32(indicies_u32).chain(convert_indicies_u16(indicies_u16)))
+}
+
+/// Convert the plain `u32` indicies to a format that trimesh ingests.
+///
+/// The trailing indicies that do not form a whole triangle are ignored.
+pub fn convert_indicies_u32(indicies: &[u32]) -> impl Iterator<Item = [u32; 3]> + '_ {
+    indicies
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+}
+
+/// Convert the plain `u16` indicies to a format that trimesh ingests,
+/// widening them to `u32`.
+///
+/// The trailing indicies that do not form a whole triangle are ignored.
+pub fn convert_indicies_u16(indicies: &[u16]) -> impl Iterator<Item = [u32; 3]> + '_ {
+    indicies
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]].map(u32::from))
 }
 
 /// The an error while building the [`TriMesh`] geometry from a [`Mesh`].
@@ -71,6 +214,85 @@ pub enum TriMeshBuildError {
     /// Indicies conversion failed.
     #[error("indicies: {0}")]
     UnsupportedIndexFormat(#[source] UnsupportedFormatError),
+    /// An index refers to a vertex past the end of the vertex data.
+    #[error("index {index} is out of bounds for {len} verticies")]
+    IndexOutOfBounds {
+        /// The offending index.
+        index: usize,
+        /// The amount of verticies available.
+        len: usize,
+    },
+    /// A vertex lies outside of the allowed bounds.
+    #[error("vertex {index} is out of bounds along the axis {coord}")]
+    OutOfBounds {
+        /// The index of the offending vertex.
+        index: usize,
+        /// The axis along which the vertex is out of bounds, `0` for `x`,
+        /// `1` for `y` and `2` for `z`.
+        coord: usize,
+    },
+    /// The geometry has no triangles.
+    #[error("the geometry has no triangles")]
+    EmptyGeometry,
+    /// The mesh is made of points, lines or triangle strips rather than
+    /// a triangle list.
+    #[error("unsupported {0:?} topology")]
+    UnsupportedTopology(PrimitiveTopology),
+    /// The mesh expands to more triangles than allowed.
+    #[error("the mesh expands to {triangles} triangles, the limit is {limit}")]
+    ExpansionLimitExceeded {
+        /// The amount of triangles the mesh expands to.
+        triangles: usize,
+        /// The maximum amount of the expanded triangles.
+        limit: usize,
+    },
+    /// The geometry has inconsistent topology for the requested
+    /// [`TriMeshFlags`].
+    #[error("topology: {0}")]
+    Topology(#[source] TopologyError),
+    /// A vertex attribute has a different amount of values than there are
+    /// vertex positions.
+    #[error("attribute {attribute:?} has {len} values, expected {expected}")]
+    InconsistentAttributeLengths {
+        /// The offending attribute.
+        attribute: MeshVertexAttributeId,
+        /// The amount of values the attribute has.
+        len: usize,
+        /// The amount of vertex positions.
+        expected: usize,
+    },
+    /// A vertex attribute required for the operation is missing.
+    #[error("attribute {0:?} is missing")]
+    MissingAttribute(MeshVertexAttributeId),
+    /// A joint index refers to a joint past the end of the joint matrices.
+    #[error("joint {joint} is out of bounds for {len} joint matrices")]
+    JointOutOfBounds {
+        /// The offending joint index.
+        joint: usize,
+        /// The amount of joint matrices available.
+        len: usize,
+    },
+    /// Welding can not bring the vertex count down to the target without
+    /// collapsing all the triangles.
+    #[error("welding can not reach {target} verticies")]
+    WeldTargetUnreachable {
+        /// The target amount of verticies.
+        target: usize,
+    },
+    /// No LOD meshes were given to select from.
+    #[error("no LOD meshes given")]
+    NoLods,
+}
+
+/// The amount of verticies the vertex position values hold.
+///
+/// The flat [`VertexAttributeValues::Float32`] values hold a vertex per
+/// three values.
+pub(crate) fn vertex_count(positions: &VertexAttributeValues) -> usize {
+    match positions {
+        VertexAttributeValues::Float32(val) => val.len() / 3,
+        positions => positions.len(),
+    }
 }
 
 /// Prepare the inputs to the [`TriMesh`] constructor from the [`Mesh`]
@@ -79,6 +301,17 @@ pub enum TriMeshBuildError {
 /// The use of iterators instead of copied [`Vec`]s allows adding a layer of
 /// transformations over the output data before doing a `collect` to populate
 /// the cache.
+///
+/// Meshes without any triangles are rejected with
+/// [`TriMeshBuildError::EmptyGeometry`], as a [`TriMesh`] must contain at
+/// least one triangle, and the indicies referring past the verticies are
+/// rejected with [`TriMeshBuildError::IndexOutOfBounds`].
+///
+/// Non-indexed [`PrimitiveTopology::TriangleList`] meshes, like the ones
+/// after [`Mesh::duplicate_vertices`], use each three consecutive verticies
+/// as a triangle. The meshes with any other topology than
+/// [`PrimitiveTopology::TriangleList`], including the triangle strips, are
+/// rejected with [`TriMeshBuildError::UnsupportedTopology`].
 pub fn prepare_trimesh_from_mesh(
     mesh: &Mesh,
 ) -> Result<
@@ -88,53 +321,643 @@ pub fn prepare_trimesh_from_mesh(
     ),
     TriMeshBuildError,
 > {
-    let (verticies, indicies) = extract_geometry(mesh)?;
+    let verticies = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .ok_or_else(|| ExtractGeometryError::missing_positions(mesh))?;
+    let len = vertex_count(verticies);
+    if let Some(indicies) = mesh.indices() {
+        check_index_bounds(indicies, len)?;
+    }
+    let indicies = mesh_triangles(mesh, len)?;
     let verticies =
         convert_verticies(verticies).map_err(TriMeshBuildError::UnsupportedVerexDataFormat)?;
-    let indicies = convert_indicies(indicies).map_err(TriMeshBuildError::UnsupportedIndexFormat)?;
     Ok((verticies, indicies))
 }
 
+/// The amount of the triangles the [`Mesh`] expands to beyond the ones
+/// listed by its indicies, that is the implicit triangles of
+/// the non-indexed triangle lists.
+fn expanded_triangle_count(mesh: &Mesh) -> usize {
+    match (mesh.indices(), mesh.attribute(Mesh::ATTRIBUTE_POSITION)) {
+        (None, Some(verticies)) if mesh.primitive_topology() == PrimitiveTopology::TriangleList => {
+            vertex_count(verticies) / 3
+        }
+        _ => 0,
+    }
+}
+
+/// The triangles of the [`Mesh`] with `vertex_count` verticies, either from
+/// its indicies or implied by the non-indexed triangle list.
+fn mesh_triangles(
+    mesh: &Mesh,
+    vertex_count: usize,
+) -> Result<impl Iterator<Item = [u32; 3]> + '_, TriMeshBuildError> {
+    /// Stands in for the indicies of the non-indexed meshes.
+    static NO_INDICIES: Indices = Indices::U32(Vec::new());
+
+    let topology = mesh.primitive_topology();
+    // The strips are not expanded yet, so they are rejected rather than read
+    // as the lists.
+    if topology != PrimitiveTopology::TriangleList {
+        return Err(TriMeshBuildError::UnsupportedTopology(topology));
+    }
+
+    let (indicies, implicit_triangles) = match mesh.indices() {
+        Some(indicies) => (indicies, 0),
+        None => (&NO_INDICIES, vertex_count / 3),
+    };
+    if indicies.len() < 3 && implicit_triangles == 0 {
+        return Err(TriMeshBuildError::EmptyGeometry);
+    }
+
+    let explicit = convert_indicies(indicies).map_err(TriMeshBuildError::UnsupportedIndexFormat)?;
+    let implicit = (0..implicit_triangles as u32).map(|triangle| {
+        let first = triangle * 3;
+        [first, first + 1, first + 2]
+    });
+    Ok(explicit.chain(implicit))
+}
+
+/// Prepare the inputs to the [`TriMesh`] constructor like
+/// [`prepare_trimesh_from_mesh`] does, but take the vertex positions from
+/// the given `positions` instead of the [`Mesh::ATTRIBUTE_POSITION`].
+///
+/// Useful for building a [`TriMesh`] for a specific pose, like the mesh
+/// positions with the morph target deltas applied, while reusing the mesh
+/// indicies.
+pub fn prepare_trimesh_from_mesh_with_positions<'a>(
+    mesh: &'a Mesh,
+    positions: &'a VertexAttributeValues,
+) -> Result<
+    (
+        impl Iterator<Item = Point<Real>> + 'a,
+        impl Iterator<Item = [u32; 3]> + 'a,
+    ),
+    TriMeshBuildError,
+> {
+    let len = vertex_count(positions);
+    if let Some(indicies) = mesh.indices() {
+        check_index_bounds(indicies, len)?;
+    }
+    let indicies = mesh_triangles(mesh, len)?;
+    let verticies =
+        convert_verticies(positions).map_err(TriMeshBuildError::UnsupportedVerexDataFormat)?;
+    Ok((verticies, indicies))
+}
+
+/// Prepare the inputs to the [`TriMesh`] constructor like
+/// [`prepare_trimesh_from_mesh_with_positions`] does, taking the vertex
+/// positions from the given vertex `attribute` of the [`Mesh`].
+///
+/// Useful when the collision geometry is kept in a custom attribute, apart
+/// from the render positions. The attribute has to be in one of the formats
+/// [`convert_verticies`] supports.
+pub fn prepare_trimesh_from_mesh_with_attribute(
+    mesh: &Mesh,
+    attribute: MeshVertexAttributeId,
+) -> Result<
+    (
+        impl Iterator<Item = Point<Real>> + '_,
+        impl Iterator<Item = [u32; 3]> + '_,
+    ),
+    TriMeshBuildError,
+> {
+    let positions = mesh.attribute(attribute).ok_or_else(|| {
+        if attribute == Mesh::ATTRIBUTE_POSITION.id {
+            ExtractGeometryError::missing_positions(mesh).into()
+        } else {
+            TriMeshBuildError::MissingAttribute(attribute)
+        }
+    })?;
+    prepare_trimesh_from_mesh_with_positions(mesh, positions)
+}
+
+/// Check that all the indicies refer to one of the `len` verticies.
+fn check_index_bounds(indicies: &Indices, len: usize) -> Result<(), TriMeshBuildError> {
+    match indicies.iter().max() {
+        Some(index) if index >= len => Err(TriMeshBuildError::IndexOutOfBounds { index, len }),
+        _ => Ok(()),
+    }
+}
+
 /// Create a [`TriMesh`] from the [`Mesh`] geometry.
 ///
 /// You might want to use raw [`prepare_trimesh_from_mesh`] and call
 /// [`TriMesh::new`] yourself if you need to advanced caching/translation of
 /// the verticies and/or indicies.
+///
+/// Note that [`TriMesh::new`] always builds the BVH eagerly, as `parry3d`
+/// has no way to construct a [`TriMesh`] without one. If you only need to
+/// read or merge the raw triangles, use [`CachedTriMeshBuilder`] and build
+/// the [`TriMesh`] once the geometry is final.
 pub fn trimesh_from_mesh(mesh: &Mesh) -> Result<TriMesh, TriMeshBuildError> {
-    let (verticies, indicies) = prepare_trimesh_from_mesh(mesh)?;
-    let trimesh = TriMesh::new(verticies.collect(), indicies.collect());
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    let trimesh = new_trimesh(verticies, indicies);
     Ok(trimesh)
 }
 
+/// A [`TriMesh`] that can be converted from a [`Mesh`] with [`TryFrom`].
+///
+/// The conversion is the same as [`trimesh_from_mesh`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_trimesh::{BevyTriMesh, TriMesh, TriMeshBuildError};
+/// # fn main() -> Result<(), TriMeshBuildError> {
+/// let mesh = Mesh::from(shape::Cube::new(1.0));
+/// let trimesh: BevyTriMesh = (&mesh).try_into()?;
+/// assert_eq!(trimesh.num_triangles(), 12);
+/// let trimesh: TriMesh = trimesh.into();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Deref, DerefMut)]
+pub struct BevyTriMesh(pub TriMesh);
+
+impl TryFrom<&Mesh> for BevyTriMesh {
+    type Error = TriMeshBuildError;
+
+    fn try_from(mesh: &Mesh) -> Result<Self, Self::Error> {
+        trimesh_from_mesh(mesh).map(Self)
+    }
+}
+
+impl From<BevyTriMesh> for TriMesh {
+    fn from(trimesh: BevyTriMesh) -> Self {
+        trimesh.0
+    }
+}
+
+/// Create a [`TriMesh`] from the [`Mesh`] geometry with the given
+/// [`TriMeshFlags`].
+///
+/// Unlike [`TriMesh::with_flags`], reports the topology errors.
+pub fn trimesh_from_mesh_with_flags(
+    mesh: &Mesh,
+    flags: TriMeshFlags,
+) -> Result<TriMesh, TriMeshBuildError> {
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    trimesh_with_flags(verticies, indicies, flags)
+}
+
+/// Create a [`TriMesh`] from the [`Mesh`] vertex positions and the given
+/// `triangles` in place of the [`Mesh`] indicies.
+///
+/// Every index is checked against the amount of the vertex positions.
+pub fn trimesh_from_mesh_with_indices(
+    mesh: &Mesh,
+    triangles: &[[u32; 3]],
+) -> Result<TriMesh, TriMeshBuildError> {
+    let positions = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .ok_or_else(|| ExtractGeometryError::missing_positions(mesh))?;
+    if triangles.is_empty() {
+        return Err(TriMeshBuildError::EmptyGeometry);
+    }
+    let len = vertex_count(positions);
+    if let Some(&index) = triangles
+        .iter()
+        .flatten()
+        .find(|&&index| index as usize >= len)
+    {
+        return Err(TriMeshBuildError::IndexOutOfBounds {
+            index: index as usize,
+            len,
+        });
+    }
+    let verticies = convert_verticies(positions)
+        .map_err(TriMeshBuildError::UnsupportedVerexDataFormat)?
+        .collect();
+    Ok(new_trimesh(verticies, triangles.to_vec()))
+}
+
+/// Create a [`TriMesh`] with the given [`TriMeshFlags`], reporting
+/// the topology errors.
+///
+/// Fails with [`TriMeshBuildError::EmptyGeometry`] if there are no
+/// triangles, as the flags can not be applied to the [`trimesh_empty`]
+/// placeholder.
+fn trimesh_with_flags(
+    verticies: Vec<Point<Real>>,
+    indicies: Vec<[u32; 3]>,
+    flags: TriMeshFlags,
+) -> Result<TriMesh, TriMeshBuildError> {
+    if indicies.is_empty() {
+        return Err(TriMeshBuildError::EmptyGeometry);
+    }
+    let mut trimesh = new_trimesh(verticies, indicies);
+    trimesh
+        .set_flags(flags)
+        .map_err(TriMeshBuildError::Topology)?;
+    Ok(trimesh)
+}
+
+/// The [`TriMesh`] constructor inputs.
+type Geometry = (Vec<Point<Real>>, Vec<[u32; 3]>);
+
+/// The geometry in the plain arrays.
+type RawGeometry = (Vec<[f32; 3]>, Vec<[u32; 3]>);
+
+/// The [`TriMesh`] constructor inputs borrowed from elsewhere.
+type GeometryRef<'a> = (&'a [Point<Real>], &'a [[u32; 3]]);
+
+/// Reinterpret the [`Mesh`] geometry in place if it is already in the format
+/// that trimesh ingests, that is [`VertexAttributeValues::Float32x3`]
+/// verticies and [`Indices::U32`] indicies.
+fn fast_path_geometry(mesh: &Mesh) -> Option<GeometryRef<'_>> {
+    let (VertexAttributeValues::Float32x3(verticies), Indices::U32(indicies)) =
+        extract_geometry(mesh).ok()?
+    else {
+        return None;
+    };
+    let verticies = bytemuck::try_cast_slice(verticies).ok()?;
+    let indicies = bytemuck::try_cast_slice(indicies).ok()?;
+    Some((verticies, indicies))
+}
+
+/// Collect the [`Mesh`] geometry into the [`TriMesh`] constructor inputs.
+///
+/// Meshes that are already in the trimesh format are copied over wholesale,
+/// skipping the per-element conversion of [`prepare_trimesh_from_mesh`].
+/// Traced with the `tracing` feature; the fast path returns before
+/// the `convert_verticies` and `convert_indicies` spans are entered, so only
+/// the `collect_geometry` span shows up for such meshes.
+fn collect_geometry(mesh: &Mesh) -> Result<Geometry, TriMeshBuildError> {
+    #[cfg(feature = "tracing")]
+    let _span = bevy::utils::tracing::info_span!("collect_geometry").entered();
+
+    let (verticies, indicies) = prepare_trimesh_from_mesh(mesh)?;
+    if let Some((verticies, indicies)) = fast_path_geometry(mesh) {
+        return Ok((verticies.to_vec(), indicies.to_vec()));
+    }
+    let verticies = {
+        #[cfg(feature = "tracing")]
+        let _span = bevy::utils::tracing::info_span!("convert_verticies").entered();
+        verticies.collect()
+    };
+    let indicies = {
+        #[cfg(feature = "tracing")]
+        let _span = bevy::utils::tracing::info_span!("convert_indicies").entered();
+        indicies.collect()
+    };
+    Ok((verticies, indicies))
+}
+
+/// Extract the [`Mesh`] geometry like [`prepare_trimesh_from_mesh`] does,
+/// into the owned buffers of plain arrays.
+///
+/// Useful to keep the geometry after the [`Mesh`] is gone, without depending
+/// on the `parry3d` types.
+pub fn extract_geometry_owned(mesh: &Mesh) -> Result<RawGeometry, TriMeshBuildError> {
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    let verticies = verticies.into_iter().map(Into::into).collect();
+    Ok((verticies, indicies))
+}
+
+/// How far away the [`trimesh_empty`] placeholder geometry is along each
+/// axis.
+///
+/// Far enough to never be hit in practice, and close enough for
+/// the squared distances to it to stay finite.
+const EMPTY_TRIMESH_DISTANCE: Real = 1e15;
+
+/// Create a [`TriMesh`] with no surface, to use as a placeholder, like
+/// until the actual geometry is loaded.
+///
+/// The placeholder has `num_triangles() == 1`: a [`TriMesh`] with no
+/// triangles at all panics on the point projections, so this one holds
+/// a single degenerate triangle, collapsed to a point `1e15` away from
+/// the origin along each axis. The ray casts and the intersection tests
+/// against it find nothing, while the point projections, like in
+/// [`signed_distance`], do return that far away point.
+pub fn trimesh_empty() -> TriMesh {
+    let far = Point::new(
+        EMPTY_TRIMESH_DISTANCE,
+        EMPTY_TRIMESH_DISTANCE,
+        EMPTY_TRIMESH_DISTANCE,
+    );
+    TriMesh::new(vec![far], vec![[0, 0, 0]])
+}
+
+/// Create a [`TriMesh`] with [`TriMesh::new`], or the [`trimesh_empty`]
+/// placeholder if there are no triangles, which [`TriMesh::new`] panics on.
+///
+/// Traced with the `tracing` feature, as this is where the BVH is built.
+pub(crate) fn new_trimesh(verticies: Vec<Point<Real>>, indicies: Vec<[u32; 3]>) -> TriMesh {
+    if indicies.is_empty() {
+        return trimesh_empty();
+    }
+
+    #[cfg(feature = "tracing")]
+    let _span = bevy::utils::tracing::info_span!(
+        "trimesh_new",
+        verticies = verticies.len(),
+        triangles = indicies.len()
+    )
+    .entered();
+
+    TriMesh::new(verticies, indicies)
+}
+
+/// Check whether a [`TriMesh`] can be built from the [`Mesh`].
+///
+/// Returns the same errors that building the [`TriMesh`] would,
+/// without converting the geometry.
+pub fn can_convert(mesh: &Mesh) -> Result<(), TriMeshBuildError> {
+    let _ = prepare_trimesh_from_mesh(mesh)?;
+    Ok(())
+}
+
+/// Count the triangles of the [`TriMesh`] that would be built from
+/// the [`Mesh`], without converting the geometry.
+pub fn triangle_count(mesh: &Mesh) -> Result<usize, TriMeshBuildError> {
+    can_convert(mesh)?;
+    let indicies = match (mesh.indices(), mesh.attribute(Mesh::ATTRIBUTE_POSITION)) {
+        (Some(indicies), _) => indicies.len(),
+        (None, Some(verticies)) => vertex_count(verticies),
+        (None, None) => 0,
+    };
+    Ok(indicies / 3)
+}
+
+/// Select the most detailed of the LOD meshes that fits under
+/// the `max_triangles` budget and extract its geometry.
+///
+/// The coarsest LOD is used if none of them fit the budget.
+/// Only the selected LOD geometry is extracted.
+pub fn select_lod_under_budget<'a>(
+    lods: impl IntoIterator<Item = &'a Mesh>,
+    max_triangles: usize,
+) -> Result<CachedTriMeshBuilder, TriMeshBuildError> {
+    let mut best_fit: Option<(&Mesh, usize)> = None;
+    let mut coarsest: Option<(&Mesh, usize)> = None;
+    for lod in lods {
+        let count = triangle_count(lod)?;
+        if count <= max_triangles && best_fit.is_none_or(|(_, best)| count > best) {
+            best_fit = Some((lod, count));
+        }
+        if coarsest.is_none_or(|(_, coarsest)| count < coarsest) {
+            coarsest = Some((lod, count));
+        }
+    }
+    let (lod, _) = best_fit.or(coarsest).ok_or(TriMeshBuildError::NoLods)?;
+    CachedTriMeshBuilder::from_mesh(lod)
+}
+
+/// Extract the geometry of each of the meshes into its own
+/// [`CachedTriMeshBuilder`], in the same order.
+///
+/// Each mesh gets its own result, so a mesh that fails to convert does not
+/// affect the others. With the `rayon` feature the meshes are extracted
+/// in parallel.
+pub fn extract_builders<'a>(
+    meshes: impl IntoIterator<Item = &'a Mesh>,
+) -> Vec<Result<CachedTriMeshBuilder, TriMeshBuildError>> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        let meshes: Vec<_> = meshes.into_iter().collect();
+        meshes
+            .into_par_iter()
+            .map(CachedTriMeshBuilder::from_mesh)
+            .collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        meshes
+            .into_iter()
+            .map(CachedTriMeshBuilder::from_mesh)
+            .collect()
+    }
+}
+
 /// Holds the [`TriMesh`] geometry.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CachedTriMeshBuilder {
     /// Precomputed verticies to use when constructing a [`TriMesh`].
     pub verticies: Vec<Point<Real>>,
     /// Precomputed indicies to use when constructing a [`TriMesh`].
     pub indicies: Vec<[u32; 3]>,
+    /// Per-triangle tags, like the surface types, in the same order as
+    /// the [`Self::indicies`].
+    ///
+    /// Kept aligned with the triangles by the methods removing them.
+    /// The triangles past the end of the tags are untagged, so the triangles
+    /// appended later have no tags unless they are also pushed here.
+    pub tags: Vec<u32>,
+    /// The stable ids of the triangles.
+    #[cfg_attr(feature = "serde", serde(default))]
+    ids: ids::TriangleIds,
+    /// Tracks the geometry changes and the builds.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: BuildStats,
+}
+
+/// Tracks the geometry changes and the builds of a [`CachedTriMeshBuilder`].
+///
+/// The builds only borrow the builder, hence the atomics.
+#[derive(Debug, Default)]
+struct BuildStats {
+    /// Incremented on each geometry change.
+    generation: u64,
+    /// The amount of the [`TriMesh`]es built.
+    build_count: AtomicU64,
+    /// The generation of the geometry the last [`TriMesh`] was built from.
+    built_generation: AtomicU64,
 }
 
 impl CachedTriMeshBuilder {
+    /// Create an empty [`CachedTriMeshBuilder`], to fill in later.
+    ///
+    /// Until it is filled in, [`CachedTriMeshBuilder::build`] gives
+    /// the [`trimesh_empty`] placeholder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty [`CachedTriMeshBuilder`] with the space reserved for
+    /// the given amount of verticies and triangles.
+    ///
+    /// Like with [`CachedTriMeshBuilder::new`], building it before filling it
+    /// in gives the [`trimesh_empty`] placeholder.
+    pub fn with_capacity(verticies: usize, triangles: usize) -> Self {
+        Self {
+            verticies: Vec::with_capacity(verticies),
+            indicies: Vec::with_capacity(triangles),
+            tags: Vec::new(),
+            ids: ids::TriangleIds::default(),
+            stats: BuildStats::default(),
+        }
+    }
+
+    /// Remove all the geometry, keeping the allocated buffers for reuse.
+    ///
+    /// Building a cleared builder gives the [`trimesh_empty`] placeholder.
+    pub fn clear(&mut self) {
+        self.clear_triangle_ids();
+        self.verticies.clear();
+        self.indicies.clear();
+        self.tags.clear();
+        self.mark_changed();
+    }
+
+    /// Release the excess capacity of the buffers, like after removing
+    /// a lot of the geometry.
+    pub fn shrink_to_fit(&mut self) {
+        self.verticies.shrink_to_fit();
+        self.indicies.shrink_to_fit();
+        self.tags.shrink_to_fit();
+        self.ids.shrink_to_fit();
+    }
+
+    /// The bytes held by the buffers of the verticies, the indicies,
+    /// the tags and the triangle ids, including their excess capacity.
+    pub fn memory_footprint(&self) -> usize {
+        self.verticies.capacity() * std::mem::size_of::<Point<Real>>()
+            + self.indicies.capacity() * std::mem::size_of::<[u32; 3]>()
+            + self.tags.capacity() * std::mem::size_of::<u32>()
+            + self.ids.memory_footprint()
+    }
+
+    /// The generation of the geometry, incremented by each of the methods
+    /// changing it.
+    ///
+    /// The direct changes to the fields are not tracked.
+    pub fn generation(&self) -> u64 {
+        self.stats.generation
+    }
+
+    /// The amount of the [`TriMesh`]es built so far.
+    pub fn build_count(&self) -> u64 {
+        self.stats.build_count.load(Ordering::Relaxed)
+    }
+
+    /// Check whether the geometry has changed since the last [`TriMesh`]
+    /// was built, or if none was built yet.
+    pub fn changed_since_build(&self) -> bool {
+        self.build_count() == 0
+            || self.stats.built_generation.load(Ordering::Relaxed) != self.stats.generation
+    }
+
+    /// Record a change of the geometry.
+    pub(crate) fn mark_changed(&mut self) {
+        self.stats.generation += 1;
+    }
+
+    /// Record a build of a [`TriMesh`] from the current geometry.
+    fn mark_built(&self) {
+        self.stats.build_count.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .built_generation
+            .store(self.stats.generation, Ordering::Relaxed);
+    }
+
     /// Extract the geometry from a [`Mesh`] and create
     /// a [`CachedTriMeshBuilder`].
     pub fn from_mesh(mesh: &Mesh) -> Result<Self, TriMeshBuildError> {
-        let (verticies, indicies) = prepare_trimesh_from_mesh(mesh)?;
-
         // Cache the geometry to we reuse the buffer when spawning walls.
-        let verticies: Vec<_> = verticies.collect();
-        let indicies: Vec<_> = indicies.collect();
+        let (verticies, indicies) = collect_geometry(mesh)?;
 
         Ok(Self {
             verticies,
             indicies,
+            tags: Vec::new(),
+            ids: ids::TriangleIds::default(),
+            stats: BuildStats::default(),
         })
     }
 
+    /// Extract the geometry from a [`Mesh`] and create
+    /// a [`CachedTriMeshBuilder`], unless the [`Mesh`] expands to more than
+    /// `max_expanded_triangles` triangles.
+    ///
+    /// Only the triangles synthesized for the non-indexed meshes count
+    /// towards the limit, as the indexed triangles are already backed by
+    /// the index buffer. The limit is checked before anything is allocated,
+    /// so it protects against the untrusted meshes expanding without bound.
+    pub fn from_mesh_with_expansion_limit(
+        mesh: &Mesh,
+        max_expanded_triangles: usize,
+    ) -> Result<Self, TriMeshBuildError> {
+        let triangles = expanded_triangle_count(mesh);
+        if triangles > max_expanded_triangles {
+            return Err(TriMeshBuildError::ExpansionLimitExceeded {
+                triangles,
+                limit: max_expanded_triangles,
+            });
+        }
+        Self::from_mesh(mesh)
+    }
+
+    /// Extract the geometry from a [`Mesh`] and create
+    /// a [`CachedTriMeshBuilder`] with the space reserved for the given
+    /// amount of the extra verticies and triangles.
+    ///
+    /// Avoids the reallocations when more geometry is appended later, like
+    /// with [`Self::append_mesh`].
+    pub fn from_mesh_with_capacity(
+        mesh: &Mesh,
+        extra_verticies: usize,
+        extra_triangles: usize,
+    ) -> Result<Self, TriMeshBuildError> {
+        let mut builder = Self::from_mesh(mesh)?;
+        builder.verticies.reserve_exact(extra_verticies);
+        builder.indicies.reserve_exact(extra_triangles);
+        Ok(builder)
+    }
+
+    /// Replace the geometry with the one extracted from the [`Mesh`],
+    /// reusing the already allocated buffers.
+    ///
+    /// The geometry is left intact if the extraction fails.
+    pub fn update_from_mesh(&mut self, mesh: &Mesh) -> Result<(), TriMeshBuildError> {
+        let (verticies, indicies) = prepare_trimesh_from_mesh(mesh)?;
+        self.clear();
+        if let Some((verticies, indicies)) = fast_path_geometry(mesh) {
+            self.verticies.extend_from_slice(verticies);
+            self.indicies.extend_from_slice(indicies);
+        } else {
+            self.verticies.extend(verticies);
+            self.indicies.extend(indicies);
+        }
+        Ok(())
+    }
+
+    /// Borrow the precomputed geometry, for the APIs that take slices.
+    pub fn as_parts(&self) -> (&[Point<Real>], &[[u32; 3]]) {
+        (&self.verticies, &self.indicies)
+    }
+
     /// Build a new [`TriMesh`] from the precomputed geometry.
     ///
+    /// Without any triangles, like on a [`Default`] builder, this builds
+    /// the [`trimesh_empty`] placeholder.
+    ///
     /// To be used multiple times to leverage the cached data.
     pub fn build(&self) -> TriMesh {
-        TriMesh::new(self.verticies.clone(), self.indicies.clone())
+        self.mark_built();
+        new_trimesh(self.verticies.clone(), self.indicies.clone())
+    }
+
+    /// Build a new [`TriMesh`] from the precomputed geometry with the given
+    /// [`TriMeshFlags`].
+    ///
+    /// To be used multiple times to leverage the cached data.
+    pub fn build_with_flags(&self, flags: TriMeshFlags) -> Result<TriMesh, TriMeshBuildError> {
+        self.mark_built();
+        trimesh_with_flags(self.verticies.clone(), self.indicies.clone(), flags)
+    }
+
+    /// Build a new [`TriMesh`] from the precomputed geometry with
+    /// the pseudo-normals, for the [`signed_distance`] queries.
+    ///
+    /// The pseudo-normals are computed across the shared vertex indicies, so
+    /// the geometry has to be welded and consistently wound, see
+    /// [`CachedTriMeshBuilder::weld`] and
+    /// [`CachedTriMeshBuilder::normalize_winding`].
+    pub fn build_with_pseudo_normals(&self) -> Result<TriMesh, TriMeshBuildError> {
+        self.build_with_flags(TriMeshFlags::ORIENTED)
     }
 
     /// Build a new [`TriMesh`] from the precomputed geometry, while applying
@@ -145,7 +968,747 @@ impl CachedTriMeshBuilder {
         &self,
         transform: impl Fn(Point<Real>) -> Point<Real>,
     ) -> TriMesh {
+        self.mark_built();
         let verticies = self.verticies.iter().copied().map(transform).collect();
-        TriMesh::new(verticies, self.indicies.clone())
+        new_trimesh(verticies, self.indicies.clone())
+    }
+
+    /// Build a new [`TriMesh`] from the precomputed geometry, moved by
+    /// the given [`Isometry`].
+    ///
+    /// An isometry has no scale, so the triangle winding is preserved.
+    ///
+    /// To be used multiple times to leverage the cached data.
+    pub fn build_with_isometry(&self, iso: &Isometry<Real>) -> TriMesh {
+        self.build_with_vertex_transform(|vertex| iso * vertex)
+    }
+
+    /// Build a new [`TriMesh`] from the precomputed geometry, moved by
+    /// the given [`Transform`].
+    ///
+    /// A negative scale mirrors the geometry, so the triangle winding is
+    /// swapped back to keep the faces pointing outwards.
+    ///
+    /// To be used multiple times to leverage the cached data.
+    pub fn build_with_transform(&self, transform: &Transform) -> TriMesh {
+        self.mark_built();
+        let verticies = self
+            .verticies
+            .iter()
+            .map(|&vertex| {
+                interop::vec3_to_point(transform.transform_point(interop::point_to_vec3(vertex)))
+            })
+            .collect();
+        let indicies = if transform.compute_affine().matrix3.determinant() < 0.0 {
+            self.indicies.iter().map(|&[a, b, c]| [a, c, b]).collect()
+        } else {
+            self.indicies.clone()
+        };
+        new_trimesh(verticies, indicies)
+    }
+
+    /// Build a new [`TriMesh`] from the precomputed geometry, moved by
+    /// the given [`Transform`], unless it is the same as the `last` one.
+    ///
+    /// The `last` transform is updated on each build, so calling this every
+    /// frame only rebuilds the [`TriMesh`] when the [`Transform`] changes.
+    pub fn build_if_transform_changed(
+        &self,
+        transform: &Transform,
+        last: &mut Option<Transform>,
+    ) -> Option<TriMesh> {
+        if last.as_ref() == Some(transform) {
+            return None;
+        }
+        *last = Some(*transform);
+        Some(self.build_with_transform(transform))
+    }
+
+    /// Build a new [`TriMesh`] from the precomputed geometry for each of
+    /// the given [`Transform`]s, in the same order.
+    ///
+    /// With the `rayon` feature the instances are built in parallel.
+    pub fn build_batch(&self, transforms: &[Transform]) -> Vec<TriMesh> {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            transforms
+                .par_iter()
+                .map(|transform| self.build_with_transform(transform))
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            transforms
+                .iter()
+                .map(|transform| self.build_with_transform(transform))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{mesh::MeshVertexAttribute, render_resource::VertexFormat};
+    use parry3d::{
+        math::{Isometry, Vector},
+        query::{intersection_test, Ray, RayCast},
+        shape::{Ball, Cuboid},
+    };
+
+    use super::*;
+
+    fn mesh(topology: PrimitiveTopology, verticies: usize, indicies: Option<Vec<u32>>) -> Mesh {
+        let mut mesh = Mesh::new(topology);
+        let verticies: Vec<[f32; 3]> = (0..verticies)
+            .map(|vertex| [vertex as f32, (vertex % 2) as f32, (vertex % 3) as f32])
+            .collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, verticies);
+        mesh.set_indices(indicies.map(Indices::U32));
+        mesh
+    }
+
+    #[test]
+    fn rejects_non_triangle_list_topologies() {
+        for topology in [
+            PrimitiveTopology::PointList,
+            PrimitiveTopology::LineList,
+            PrimitiveTopology::LineStrip,
+            PrimitiveTopology::TriangleStrip,
+        ] {
+            let error = Some(TriMeshBuildError::UnsupportedTopology(topology));
+            let indexed = mesh(topology, 6, Some((0..6).collect()));
+            assert_eq!(trimesh_from_mesh(&indexed).err(), error);
+            assert_eq!(can_convert(&indexed).err(), error);
+            let non_indexed = mesh(topology, 6, None);
+            assert_eq!(trimesh_from_mesh(&non_indexed).err(), error);
+        }
+    }
+
+    #[test]
+    fn empty_trimesh_has_no_surface_and_no_hits() {
+        let trimesh = trimesh_empty();
+        assert_eq!(trimesh.indices().len(), 1);
+        let area: Real = trimesh.triangles().map(|triangle| triangle.area()).sum();
+        assert_eq!(area, 0.0);
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -10.0), Vector::z());
+        assert_eq!(trimesh.cast_local_ray(&ray, Real::MAX, true), None);
+        let ball = Ball::new(100.0);
+        let hit = intersection_test(
+            &Isometry::identity(),
+            &trimesh,
+            &Isometry::identity(),
+            &ball,
+        );
+        assert_eq!(hit, Ok(false));
+        let cuboid = Cuboid::new(Vector::repeat(1e6));
+        let hit = intersection_test(
+            &Isometry::identity(),
+            &trimesh,
+            &Isometry::identity(),
+            &cuboid,
+        );
+        assert_eq!(hit, Ok(false));
+        assert!(signed_distance(&trimesh, Vec3::ZERO) > 1e14);
+    }
+
+    #[test]
+    fn places_2d_positions_at_zero_depth() {
+        let quad = mesh(
+            PrimitiveTopology::TriangleList,
+            4,
+            Some(vec![0, 1, 2, 0, 2, 3]),
+        );
+        let positions =
+            VertexAttributeValues::Float32x2(vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        let (verticies, indicies) =
+            prepare_trimesh_from_mesh_with_positions(&quad, &positions).unwrap();
+        let verticies: Vec<_> = verticies.collect();
+        assert_eq!(
+            verticies,
+            [
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+            ]
+        );
+        assert_eq!(indicies.collect::<Vec<_>>(), [[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn groups_flat_positions_in_triples() {
+        const ATTRIBUTE_COLLISION: MeshVertexAttribute =
+            MeshVertexAttribute::new("Collision", 0x7e57_c011, VertexFormat::Float32);
+        let mut quad = mesh(
+            PrimitiveTopology::TriangleList,
+            4,
+            Some(vec![0, 1, 2, 0, 2, 3]),
+        );
+        let positions = quad.attribute(Mesh::ATTRIBUTE_POSITION).unwrap();
+        let VertexAttributeValues::Float32x3(positions) = positions else {
+            unreachable!();
+        };
+        let flat: Vec<f32> = positions.iter().flatten().copied().collect();
+        quad.insert_attribute(ATTRIBUTE_COLLISION, flat);
+
+        let (expected, _) = prepare_trimesh_from_mesh(&quad).unwrap();
+        let (verticies, indicies) =
+            prepare_trimesh_from_mesh_with_attribute(&quad, ATTRIBUTE_COLLISION.id).unwrap();
+        assert_eq!(verticies.collect::<Vec<_>>(), expected.collect::<Vec<_>>());
+        assert_eq!(indicies.collect::<Vec<_>>(), [[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn builds_flat_shaded_non_indexed_meshes() {
+        let mut cube = Mesh::from(shape::Cube { size: 2.0 });
+        cube.duplicate_vertices();
+        cube.compute_flat_normals();
+        assert!(cube.indices().is_none());
+
+        let trimesh = trimesh_from_mesh(&cube).unwrap();
+        assert_eq!(trimesh.vertices().len(), 36);
+        assert_eq!(trimesh.indices().len(), 12);
+        assert_eq!(trimesh.indices()[11], [33, 34, 35]);
+        let aabb = trimesh.local_aabb();
+        assert_eq!(aabb.mins, Point::new(-1.0, -1.0, -1.0));
+        assert_eq!(aabb.maxs, Point::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn plain_indicies_convert_like_the_mesh_indicies() {
+        let indicies: Vec<u32> = vec![0, 1, 2, 2, 1, 3, 4, 5];
+        let plain: Vec<_> = convert_indicies_u32(&indicies).collect();
+        let mesh: Vec<_> = convert_indicies(&Indices::U32(indicies.clone()))
+            .unwrap()
+            .collect();
+        assert_eq!(plain, [[0, 1, 2], [2, 1, 3]]);
+        assert_eq!(plain, mesh);
+
+        let narrow: Vec<u16> = indicies.iter().map(|&index| index as u16).collect();
+        assert_eq!(convert_indicies_u16(&narrow).collect::<Vec<_>>(), plain);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn traces_the_conversion_only_off_the_fast_path() {
+        use std::sync::{Arc, Mutex};
+
+        use bevy::utils::tracing::{
+            span::{Attributes, Id, Record},
+            subscriber::with_default,
+            Event, Metadata, Subscriber,
+        };
+
+        /// Records the names of the entered spans.
+        #[derive(Clone, Default)]
+        struct Recorder {
+            /// The names of the created spans, indexed by their ids.
+            spans: Arc<Mutex<Vec<&'static str>>>,
+            /// The names of the entered spans, in order.
+            entered: Arc<Mutex<Vec<&'static str>>>,
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut spans = self.spans.lock().unwrap();
+                spans.push(span.metadata().name());
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, span: &Id) {
+                let name = self.spans.lock().unwrap()[span.into_u64() as usize - 1];
+                self.entered.lock().unwrap().push(name);
+            }
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        let entered = |mesh: &Mesh| {
+            let recorder = Recorder::default();
+            with_default(recorder.clone(), || extract_geometry_owned(mesh).unwrap());
+            let entered = recorder.entered.lock().unwrap().clone();
+            entered
+        };
+
+        let mut cube = Mesh::from(shape::Cube { size: 1.0 });
+        assert_eq!(entered(&cube), ["collect_geometry"]);
+        let Some(Indices::U32(indicies)) = cube.indices() else {
+            unreachable!();
+        };
+        let indicies = indicies.iter().map(|&index| index as u16).collect();
+        cube.set_indices(Some(Indices::U16(indicies)));
+        assert_eq!(
+            entered(&cube),
+            ["collect_geometry", "convert_verticies", "convert_indicies"]
+        );
+    }
+
+    /// Makes up the reproducible malformed meshes.
+    struct SplitMix(u64);
+
+    impl SplitMix {
+        fn below(&mut self, bound: u64) -> u64 {
+            cleanup::splitmix64(&mut self.0) % bound.max(1)
+        }
+    }
+
+    fn malformed_mesh(random: &mut SplitMix) -> Mesh {
+        const TOPOLOGIES: [PrimitiveTopology; 5] = [
+            PrimitiveTopology::PointList,
+            PrimitiveTopology::LineList,
+            PrimitiveTopology::LineStrip,
+            PrimitiveTopology::TriangleList,
+            PrimitiveTopology::TriangleStrip,
+        ];
+        const SPECIALS: [f32; 6] = [
+            f32::NAN,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            -0.0,
+            1e30,
+            1e-40,
+        ];
+
+        let mut mesh = Mesh::new(TOPOLOGIES[random.below(5) as usize]);
+        let verticies = random.below(12) as usize;
+        if random.below(10) > 0 {
+            let positions: Vec<[f32; 3]> = (0..verticies)
+                .map(|_| {
+                    [0; 3].map(|_| match random.below(8) {
+                        0 => SPECIALS[random.below(6) as usize],
+                        _ => random.below(2000) as f32 / 100.0 - 10.0,
+                    })
+                })
+                .collect();
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        }
+        // The lengths are not multiples of 3 most of the time, and some of
+        // the indicies point past the verticies.
+        let len = random.below(20);
+        let bound = verticies as u64 + 2;
+        match random.below(4) {
+            0 => {}
+            1 => mesh.set_indices(Some(Indices::U16(
+                (0..len).map(|_| random.below(bound) as u16).collect(),
+            ))),
+            _ => mesh.set_indices(Some(Indices::U32(
+                (0..len)
+                    .map(|_| match random.below(20) {
+                        0 => u32::MAX,
+                        _ => random.below(bound) as u32,
+                    })
+                    .collect(),
+            ))),
+        }
+        mesh
+    }
+
+    #[test]
+    fn rejects_malformed_meshes_without_panicking() {
+        let mut random = SplitMix(0x5eed);
+        for _ in 0..5000 {
+            let mesh = malformed_mesh(&mut random);
+            let trimesh = match trimesh_from_mesh(&mesh) {
+                Ok(trimesh) => trimesh,
+                Err(error) => {
+                    assert_eq!(can_convert(&mesh), Err(error));
+                    continue;
+                }
+            };
+            assert_eq!(can_convert(&mesh), Ok(()));
+            assert_eq!(mesh.primitive_topology(), PrimitiveTopology::TriangleList);
+            let len = trimesh.vertices().len() as u32;
+            assert!(!trimesh.indices().is_empty());
+            assert!(trimesh.indices().iter().flatten().all(|&index| index < len));
+        }
+    }
+
+    #[test]
+    fn builds_the_collider_at_the_given_pose() {
+        let cube = Mesh::from(shape::Cube { size: 2.0 });
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            cube.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            unreachable!();
+        };
+        let posed = VertexAttributeValues::Float32x3(
+            positions
+                .iter()
+                .map(|&[x, y, z]| [x, y * 2.0 + 3.0, z])
+                .collect(),
+        );
+
+        let (verticies, indicies) =
+            prepare_trimesh_from_mesh_with_positions(&cube, &posed).unwrap();
+        let trimesh = TriMesh::new(verticies.collect(), indicies.collect());
+        let aabb = trimesh.local_aabb();
+        assert_eq!(aabb.mins, Point::new(-1.0, 1.0, -1.0));
+        assert_eq!(aabb.maxs, Point::new(1.0, 5.0, 1.0));
+        assert_eq!(
+            trimesh.indices(),
+            trimesh_from_mesh(&cube).unwrap().indices()
+        );
+    }
+
+    #[test]
+    fn can_convert_reports_the_build_errors() {
+        let valid = mesh(PrimitiveTopology::TriangleList, 3, Some(vec![0, 1, 2]));
+        let no_positions = Mesh::new(PrimitiveTopology::TriangleList);
+        let out_of_bounds = mesh(PrimitiveTopology::TriangleList, 3, Some(vec![0, 1, 3]));
+        let empty = mesh(PrimitiveTopology::TriangleList, 3, Some(vec![0, 1]));
+        let strip = mesh(PrimitiveTopology::TriangleStrip, 4, None);
+        for mesh in [valid, no_positions, out_of_bounds, empty, strip] {
+            assert_eq!(can_convert(&mesh), trimesh_from_mesh(&mesh).map(|_| ()));
+        }
+    }
+
+    #[test]
+    fn reports_the_inconsistent_winding() {
+        let mut flipped = mesh(PrimitiveTopology::TriangleList, 4, None);
+        flipped.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, -1.0, 0.0],
+            ],
+        );
+        // Both triangles walk the shared edge from 0 to 1.
+        flipped.set_indices(Some(Indices::U32(vec![0, 1, 2, 0, 1, 3])));
+        // The winding is only checked along with the half-edge topology.
+        let flags = TriMeshFlags::ORIENTED | TriMeshFlags::HALF_EDGE_TOPOLOGY;
+        let error = trimesh_from_mesh_with_flags(&flipped, flags).err();
+        assert!(
+            matches!(error, Some(TriMeshBuildError::Topology(_))),
+            "{error:?}"
+        );
+
+        flipped.set_indices(Some(Indices::U32(vec![0, 1, 2, 1, 0, 3])));
+        assert!(trimesh_from_mesh_with_flags(&flipped, flags).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_index_buffers() {
+        let empty = mesh(PrimitiveTopology::TriangleList, 3, Some(vec![]));
+        assert_eq!(
+            trimesh_from_mesh(&empty).err(),
+            Some(TriMeshBuildError::EmptyGeometry)
+        );
+        let mut narrow = empty;
+        narrow.set_indices(Some(Indices::U16(vec![])));
+        assert_eq!(
+            CachedTriMeshBuilder::from_mesh(&narrow).err(),
+            Some(TriMeshBuildError::EmptyGeometry)
+        );
+    }
+
+    #[test]
+    fn updating_from_a_smaller_mesh_keeps_the_buffers() {
+        let sphere = Mesh::from(shape::UVSphere::default());
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        let mut builder = CachedTriMeshBuilder::from_mesh(&sphere).unwrap();
+        let capacities = (builder.verticies.capacity(), builder.indicies.capacity());
+
+        builder.update_from_mesh(&cube).unwrap();
+        assert_eq!(
+            (builder.verticies.capacity(), builder.indicies.capacity()),
+            capacities
+        );
+        let expected = CachedTriMeshBuilder::from_mesh(&cube).unwrap();
+        assert_eq!(builder.as_parts(), expected.as_parts());
+    }
+
+    #[test]
+    fn clearing_keeps_the_capacity() {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        let capacities = (builder.verticies.capacity(), builder.indicies.capacity());
+        builder.clear();
+        assert!(builder.as_parts().0.is_empty() && builder.as_parts().1.is_empty());
+        assert_eq!(
+            (builder.verticies.capacity(), builder.indicies.capacity()),
+            capacities
+        );
+        assert_eq!(builder.build().indices().len(), 1);
+
+        let builder = CachedTriMeshBuilder::with_capacity(8, 12);
+        assert_eq!(builder.build().indices().len(), 1);
+    }
+
+    #[test]
+    fn builds_the_rotated_geometry() {
+        let triangle = mesh(PrimitiveTopology::TriangleList, 3, None);
+        let builder = CachedTriMeshBuilder::from_mesh(&triangle).unwrap();
+        // A quarter turn around the y axis takes x to -z.
+        let iso = Isometry::rotation(Vector::y() * std::f32::consts::FRAC_PI_2);
+        let trimesh = builder.build_with_isometry(&iso);
+
+        assert_eq!(builder.verticies[1], Point::new(1.0, 1.0, 1.0));
+        let vertex = trimesh.vertices()[1];
+        assert!(
+            (vertex - Point::new(1.0, 1.0, -1.0)).norm() < 1e-6,
+            "{vertex}"
+        );
+        assert_eq!(trimesh.indices(), &[[0, 1, 2]]);
+    }
+
+    #[test]
+    fn builds_with_the_given_triangles() {
+        let quad = mesh(
+            PrimitiveTopology::TriangleList,
+            4,
+            Some(vec![0, 1, 2, 0, 2, 3]),
+        );
+        let remapped = [[2, 1, 0], [3, 2, 0]];
+        let trimesh = trimesh_from_mesh_with_indices(&quad, &remapped).unwrap();
+        assert_eq!(trimesh.indices(), &remapped);
+        assert_eq!(
+            trimesh.vertices(),
+            trimesh_from_mesh(&quad).unwrap().vertices()
+        );
+
+        assert_eq!(
+            trimesh_from_mesh_with_indices(&quad, &[[0, 1, 4]]).err(),
+            Some(TriMeshBuildError::IndexOutOfBounds { index: 4, len: 4 })
+        );
+        assert_eq!(
+            trimesh_from_mesh_with_indices(&quad, &[]).err(),
+            Some(TriMeshBuildError::EmptyGeometry)
+        );
+    }
+
+    #[test]
+    fn reserves_the_extra_capacity() {
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        let builder = CachedTriMeshBuilder::from_mesh_with_capacity(&cube, 100, 50).unwrap();
+        assert!(builder.verticies.capacity() >= 24 + 100);
+        assert!(builder.indicies.capacity() >= 12 + 50);
+        assert_eq!(
+            builder.as_parts(),
+            CachedTriMeshBuilder::from_mesh(&cube).unwrap().as_parts()
+        );
+    }
+
+    #[test]
+    fn builds_once_per_transform_change() {
+        let builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        let mut last = None;
+        let transform = Transform::from_xyz(1.0, 2.0, 3.0);
+        assert!(builder
+            .build_if_transform_changed(&transform, &mut last)
+            .is_some());
+        assert!(builder
+            .build_if_transform_changed(&transform, &mut last)
+            .is_none());
+        assert_eq!(builder.build_count(), 1);
+        assert_eq!(last, Some(transform));
+
+        let moved = transform.with_scale(Vec3::splat(2.0));
+        let trimesh = builder.build_if_transform_changed(&moved, &mut last);
+        assert_eq!(
+            trimesh.unwrap().local_aabb().maxs,
+            Point::new(3.0, 4.0, 5.0)
+        );
+        assert_eq!(builder.build_count(), 2);
+    }
+
+    #[test]
+    fn owned_geometry_matches_the_mesh_buffers() {
+        let mut cube = Mesh::from(shape::Cube::new(2.0));
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            cube.attribute(Mesh::ATTRIBUTE_POSITION).cloned()
+        else {
+            unreachable!();
+        };
+        let Some(Indices::U32(indicies)) = cube.indices().cloned() else {
+            unreachable!();
+        };
+        let triangles: Vec<[u32; 3]> = indicies
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+
+        let (verticies, owned) = extract_geometry_owned(&cube).unwrap();
+        assert_eq!(verticies, positions);
+        assert_eq!(owned, triangles);
+
+        let narrow = indicies.iter().map(|&index| index as u16).collect();
+        cube.set_indices(Some(Indices::U16(narrow)));
+        assert_eq!(extract_geometry_owned(&cube), Ok((positions, triangles)));
+    }
+
+    #[test]
+    fn counts_the_changes_and_the_builds() {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        let generation = builder.generation();
+        assert_eq!(builder.build_count(), 0);
+        assert!(builder.changed_since_build());
+
+        builder.build();
+        assert_eq!(builder.build_count(), 1);
+        assert!(!builder.changed_since_build());
+
+        builder.weld(1e-4);
+        builder.prune_unused_vertices();
+        assert_eq!(builder.generation(), generation + 2);
+        assert!(builder.changed_since_build());
+
+        builder.build_with_isometry(&Isometry::identity());
+        assert_eq!(builder.build_count(), 2);
+        assert!(!builder.changed_since_build());
+    }
+
+    #[test]
+    fn takes_the_positions_from_a_custom_attribute() {
+        const ATTRIBUTE_HULL: MeshVertexAttribute =
+            MeshVertexAttribute::new("Hull", 0x7e57_4011, VertexFormat::Float32x3);
+        let mut cube = Mesh::from(shape::Cube::new(2.0));
+        assert_eq!(
+            prepare_trimesh_from_mesh_with_attribute(&cube, ATTRIBUTE_HULL.id).err(),
+            Some(TriMeshBuildError::MissingAttribute(ATTRIBUTE_HULL.id))
+        );
+
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            cube.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            unreachable!();
+        };
+        let hull: Vec<[f32; 3]> = positions.iter().map(|&[x, y, z]| [x * 3.0, y, z]).collect();
+        cube.insert_attribute(ATTRIBUTE_HULL, hull);
+        let (verticies, indicies) =
+            prepare_trimesh_from_mesh_with_attribute(&cube, ATTRIBUTE_HULL.id).unwrap();
+        let trimesh = TriMesh::new(verticies.collect(), indicies.collect());
+        assert_eq!(trimesh.local_aabb().maxs, Point::new(3.0, 1.0, 1.0));
+        assert_eq!(
+            trimesh.indices(),
+            trimesh_from_mesh(&cube).unwrap().indices()
+        );
+    }
+
+    #[test]
+    fn builds_an_instance_per_transform() {
+        let builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        let offsets = [-4.0, 0.0, 4.0];
+        let transforms: Vec<_> = offsets
+            .iter()
+            .map(|&x| Transform::from_xyz(x, 0.0, 0.0))
+            .collect();
+        let trimeshes = builder.build_batch(&transforms);
+        assert_eq!(trimeshes.len(), 3);
+        for (trimesh, x) in trimeshes.iter().zip(offsets) {
+            let aabb = trimesh.local_aabb();
+            assert_eq!(aabb.mins, Point::new(x - 1.0, -1.0, -1.0));
+            assert_eq!(aabb.maxs, Point::new(x + 1.0, 1.0, 1.0));
+        }
+        assert_eq!(builder.build_count(), 3);
+    }
+
+    #[test]
+    fn tells_the_stripped_meshes_apart() {
+        let stripped = Mesh::new(PrimitiveTopology::TriangleList);
+        let error = TriMeshBuildError::from(ExtractGeometryError::GeometryNotCpuAvailable);
+        assert_eq!(trimesh_from_mesh(&stripped).err(), Some(error));
+
+        let mut normals_only = Mesh::new(PrimitiveTopology::TriangleList);
+        normals_only.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; 3]);
+        let error = TriMeshBuildError::from(ExtractGeometryError::NoVertexPositionData);
+        assert_eq!(trimesh_from_mesh(&normals_only).err(), Some(error));
+    }
+
+    #[test]
+    fn extracts_the_builders_in_order() {
+        let meshes = [
+            Mesh::from(shape::Cube::new(2.0)),
+            Mesh::new(PrimitiveTopology::TriangleList),
+            mesh(PrimitiveTopology::TriangleList, 3, None),
+            mesh(PrimitiveTopology::LineList, 4, None),
+        ];
+        let triangles: Vec<_> = extract_builders(&meshes)
+            .into_iter()
+            .map(|builder| builder.map(|builder| builder.indicies.len()))
+            .collect();
+        assert_eq!(
+            triangles,
+            [
+                Ok(12),
+                Err(ExtractGeometryError::GeometryNotCpuAvailable.into()),
+                Ok(1),
+                Err(TriMeshBuildError::UnsupportedTopology(
+                    PrimitiveTopology::LineList
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn shrinking_reduces_the_footprint() {
+        let mut builder =
+            CachedTriMeshBuilder::from_mesh(&shape::UVSphere::default().into()).unwrap();
+        builder.weld(1e-4);
+        builder.prune_unused_vertices();
+        builder.indicies.truncate(10);
+        let footprint = builder.memory_footprint();
+
+        builder.shrink_to_fit();
+        assert!(builder.memory_footprint() < footprint);
+        assert!(builder.indicies.capacity() < 100);
+    }
+
+    #[test]
+    fn limits_the_expanded_triangles() {
+        let expanded = mesh(PrimitiveTopology::TriangleList, 30, None);
+        assert_eq!(
+            CachedTriMeshBuilder::from_mesh_with_expansion_limit(&expanded, 9).err(),
+            Some(TriMeshBuildError::ExpansionLimitExceeded {
+                triangles: 10,
+                limit: 9,
+            })
+        );
+        let builder = CachedTriMeshBuilder::from_mesh_with_expansion_limit(&expanded, 10).unwrap();
+        assert_eq!(builder.indicies.len(), 10);
+
+        // The indexed triangles do not count.
+        let indexed = mesh(PrimitiveTopology::TriangleList, 30, Some((0..30).collect()));
+        assert!(CachedTriMeshBuilder::from_mesh_with_expansion_limit(&indexed, 0).is_ok());
+    }
+
+    #[test]
+    fn builds_the_placeholder_without_triangles() {
+        let builder = CachedTriMeshBuilder::default();
+        assert_eq!(builder.build().indices().len(), 1);
+        let transform = Transform::from_xyz(1.0, 2.0, 3.0);
+        assert_eq!(builder.build_with_transform(&transform).indices().len(), 1);
+        let batch = builder.build_batch(&[transform, Transform::IDENTITY]);
+        assert_eq!(batch.len(), 2);
+        assert!(batch.iter().all(|trimesh| trimesh.indices().len() == 1));
+        assert_eq!(
+            builder.build_with_flags(TriMeshFlags::empty()).err(),
+            Some(TriMeshBuildError::EmptyGeometry)
+        );
+    }
+
+    #[test]
+    fn keeps_the_faces_outwards_when_mirrored() {
+        let builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        let volume =
+            |trimesh: &TriMesh| topology::signed_volume(trimesh.vertices(), trimesh.indices());
+        let identity = builder.build_with_transform(&Transform::IDENTITY);
+        assert!(volume(&identity) > 0.0);
+
+        let mirrored =
+            builder.build_with_transform(&Transform::from_scale(Vec3::new(-1.0, 1.0, 1.0)));
+        assert!((volume(&mirrored) - volume(&identity)).abs() < 1e-4);
     }
 }
@@ -0,0 +1,294 @@
+//! Accumulating the [`TriMesh`] geometry from multiple sources.
+
+use std::ops::Range;
+
+use bevy::prelude::*;
+use parry3d::math::{Point, Real};
+
+use crate::{
+    interop::{point_to_vec3, vec3_to_point},
+    prepare_trimesh_from_mesh, CachedTriMeshBuilder, TriMesh, TriMeshBuildError,
+};
+
+/// The part of the [`CachedTriMeshBuilder`] geometry appended at once, to
+/// remove it later with [`CachedTriMeshBuilder::remove_region`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshRegion {
+    /// The verticies of the region.
+    pub vertex_range: Range<usize>,
+    /// The triangles of the region.
+    pub triangle_range: Range<usize>,
+}
+
+impl MeshRegion {
+    /// Update the region after the `removed` region was removed from
+    /// the same [`CachedTriMeshBuilder`], as the geometry past the removed
+    /// region moves back.
+    pub fn rebase(&mut self, removed: &MeshRegion) {
+        /// Move the `range` back if it is past the `removed` range.
+        fn rebase(range: &mut Range<usize>, removed: &Range<usize>) {
+            if range.start >= removed.end {
+                let len = removed.len();
+                *range = range.start - len..range.end - len;
+            }
+        }
+        rebase(&mut self.vertex_range, &removed.vertex_range);
+        rebase(&mut self.triangle_range, &removed.triangle_range);
+    }
+}
+
+/// An error indicating a [`MeshRegion`] can not be removed.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum RegionError {
+    /// The region is past the end of the geometry.
+    #[error("the region is out of bounds")]
+    OutOfBounds,
+    /// A triangle outside the region uses a vertex of the region.
+    #[error("vertex {0} of the region is used outside of it")]
+    VertexInUse(u32),
+}
+
+impl CachedTriMeshBuilder {
+    /// Append the geometry of the [`TriMesh`], rebasing its indicies past
+    /// the verticies already present.
+    pub fn extend_from_trimesh(&mut self, trimesh: &TriMesh) -> MeshRegion {
+        self.append(
+            trimesh.vertices().iter().copied(),
+            trimesh.indices().iter().copied(),
+        )
+    }
+
+    /// Append the geometry extracted from the [`Mesh`] moved by
+    /// the `transform`, rebasing its indicies past the verticies already
+    /// present.
+    ///
+    /// A [`Transform`] converts into a [`GlobalTransform`] with
+    /// [`GlobalTransform::from`]. The triangles are rewound if
+    /// the `transform` mirrors the geometry, like with a negative scale,
+    /// so that they keep facing outward. The geometry is left intact if
+    /// the extraction fails.
+    pub fn append_mesh_with_transform(
+        &mut self,
+        mesh: &Mesh,
+        transform: &GlobalTransform,
+    ) -> Result<MeshRegion, TriMeshBuildError> {
+        let (verticies, indicies) = prepare_trimesh_from_mesh(mesh)?;
+        let verticies =
+            verticies.map(|vertex| vec3_to_point(transform.transform_point(point_to_vec3(vertex))));
+        let mirrored = transform.affine().matrix3.determinant() < 0.0;
+        let indicies = indicies.map(move |[a, b, c]| if mirrored { [a, c, b] } else { [a, b, c] });
+        Ok(self.append(verticies, indicies))
+    }
+
+    /// Extract the geometry from the [`Mesh`]es moved by their
+    /// [`Transform`]s and merge it into a [`CachedTriMeshBuilder`].
+    ///
+    /// Each part is appended as with
+    /// [`CachedTriMeshBuilder::append_mesh_with_transform`].
+    pub fn from_meshes_with_transforms<'a>(
+        items: impl IntoIterator<Item = (&'a Mesh, Transform)>,
+    ) -> Result<Self, TriMeshBuildError> {
+        Self::from_meshes_with_global_transforms(
+            items
+                .into_iter()
+                .map(|(mesh, transform)| (mesh, transform.into())),
+        )
+    }
+
+    /// Extract the geometry from the [`Mesh`]es moved by their
+    /// [`GlobalTransform`]s and merge it into a [`CachedTriMeshBuilder`].
+    pub(crate) fn from_meshes_with_global_transforms<'a>(
+        items: impl IntoIterator<Item = (&'a Mesh, GlobalTransform)>,
+    ) -> Result<Self, TriMeshBuildError> {
+        let mut builder = Self::new();
+        for (mesh, transform) in items {
+            builder.append_mesh_with_transform(mesh, &transform)?;
+        }
+        Ok(builder)
+    }
+
+    /// Append the geometry extracted from the [`Mesh`], rebasing its
+    /// indicies past the verticies already present.
+    ///
+    /// The geometry is left intact if the extraction fails.
+    pub fn append_mesh(&mut self, mesh: &Mesh) -> Result<MeshRegion, TriMeshBuildError> {
+        let (verticies, indicies) = prepare_trimesh_from_mesh(mesh)?;
+        Ok(self.append(verticies, indicies))
+    }
+
+    /// Remove the geometry of the `region`, moving back the geometry past it.
+    ///
+    /// The other regions of this builder past the removed one have to be
+    /// [`MeshRegion::rebase`]d to stay valid. The geometry is left intact if
+    /// the region is out of bounds, or if its verticies are used by
+    /// the triangles outside of it.
+    pub fn remove_region(&mut self, region: &MeshRegion) -> Result<(), RegionError> {
+        let MeshRegion {
+            vertex_range,
+            triangle_range,
+        } = region;
+        if vertex_range.start > vertex_range.end
+            || triangle_range.start > triangle_range.end
+            || vertex_range.end > self.verticies.len()
+            || triangle_range.end > self.indicies.len()
+        {
+            return Err(RegionError::OutOfBounds);
+        }
+        let outside = self.indicies[..triangle_range.start]
+            .iter()
+            .chain(&self.indicies[triangle_range.end..]);
+        if let Some(&index) = outside
+            .flatten()
+            .find(|&&index| vertex_range.contains(&(index as usize)))
+        {
+            return Err(RegionError::VertexInUse(index));
+        }
+
+        self.verticies.drain(vertex_range.clone());
+        self.retain_triangles(|triangle, _| !triangle_range.contains(&triangle));
+        let removed = vertex_range.len() as u32;
+        for index in self.indicies.iter_mut().flatten() {
+            if *index as usize >= vertex_range.end {
+                *index -= removed;
+            }
+        }
+        debug_assert_eq!(self.validate_invariant(), Ok(()));
+        self.mark_changed();
+        Ok(())
+    }
+
+    /// Append the geometry, rebasing the indicies past the verticies already
+    /// present.
+    fn append(
+        &mut self,
+        verticies: impl Iterator<Item = Point<Real>>,
+        indicies: impl Iterator<Item = [u32; 3]>,
+    ) -> MeshRegion {
+        let vertex_start = self.verticies.len();
+        let triangle_start = self.indicies.len();
+        let base = vertex_start as u32;
+        self.verticies.extend(verticies);
+        self.indicies
+            .extend(indicies.map(|triangle| triangle.map(|index| base + index)));
+        self.mark_changed();
+        MeshRegion {
+            vertex_range: vertex_start..self.verticies.len(),
+            triangle_range: triangle_start..self.indicies.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::shape;
+
+    use super::*;
+
+    #[test]
+    fn merges_the_cubes_at_their_transforms() {
+        let cube = Mesh::from(shape::Cube { size: 2.0 });
+        let plank = Mesh::from(shape::Box::new(4.0, 2.0, 2.0));
+        let builder = CachedTriMeshBuilder::from_meshes_with_transforms([
+            (
+                &cube,
+                Transform::from_xyz(-5.0, 0.0, 0.0).with_scale(Vec3::new(-1.0, 1.0, 1.0)),
+            ),
+            (&cube, Transform::from_xyz(5.0, 0.0, 0.0)),
+            // Turned upright, spanning from 3 to 7 along the y axis.
+            (
+                &plank,
+                Transform::from_xyz(0.0, 5.0, 0.0)
+                    .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+            ),
+        ])
+        .unwrap();
+        let trimesh = builder.build();
+        let aabb = trimesh.local_aabb();
+        assert!((aabb.mins - Point::new(-6.0, -1.0, -1.0)).norm() < 1e-5);
+        assert!((aabb.maxs - Point::new(6.0, 7.0, 1.0)).norm() < 1e-5);
+        assert_eq!(trimesh.indices().len(), 36);
+
+        // The mirrored cube faces outwards too, so no part cancels out
+        // another one: 8 for each cube and 16 for the plank.
+        let volume = crate::topology::signed_volume(trimesh.vertices(), trimesh.indices());
+        assert!((volume - 32.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn extends_a_cube_with_a_tetrahedron() {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        let tetrahedron = TriMesh::new(
+            vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(0.0, 0.0, 1.0),
+            ],
+            vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]],
+        );
+
+        let region = builder.extend_from_trimesh(&tetrahedron);
+        assert_eq!(region.vertex_range, 24..28);
+        assert_eq!(region.triangle_range, 12..16);
+        assert_eq!(&builder.verticies[24..], tetrahedron.vertices());
+        assert_eq!(
+            builder.indicies[12..],
+            [[24, 26, 25], [24, 25, 27], [24, 27, 26], [25, 26, 27]]
+        );
+        assert_eq!(builder.build().indices().len(), 16);
+    }
+
+    #[test]
+    fn removes_the_middle_chunk() {
+        let cube = Mesh::from(shape::Cube { size: 2.0 });
+        let mut builder = CachedTriMeshBuilder::new();
+        let mut regions: Vec<_> = [-5.0, 0.0, 5.0]
+            .into_iter()
+            .map(|x| {
+                let transform = Transform::from_xyz(x, 0.0, 0.0).into();
+                builder
+                    .append_mesh_with_transform(&cube, &transform)
+                    .unwrap()
+            })
+            .collect();
+        let last = builder.as_parts().1[24..].to_vec();
+
+        let middle = regions.remove(1);
+        builder.remove_region(&middle).unwrap();
+        for region in &mut regions {
+            region.rebase(&middle);
+        }
+
+        assert_eq!(regions[0].triangle_range, 0..12);
+        assert_eq!(regions[1].vertex_range, 24..48);
+        assert_eq!(regions[1].triangle_range, 12..24);
+        let rebased: Vec<_> = last
+            .iter()
+            .map(|triangle| triangle.map(|index| index - 24))
+            .collect();
+        assert_eq!(builder.indicies[12..], rebased);
+        assert!(builder.verticies[24..].iter().all(|vertex| vertex.x >= 4.0));
+
+        // The spliced geometry is the same as merged without the middle chunk.
+        let without_middle = CachedTriMeshBuilder::from_meshes_with_transforms([
+            (&cube, Transform::from_xyz(-5.0, 0.0, 0.0)),
+            (&cube, Transform::from_xyz(5.0, 0.0, 0.0)),
+        ])
+        .unwrap();
+        assert_eq!(builder.as_parts(), without_middle.as_parts());
+        assert_eq!(builder.build().indices().len(), 24);
+
+        // The verticies of the first chunk paired with the triangles of
+        // the last one.
+        let mismatched = MeshRegion {
+            vertex_range: regions[0].vertex_range.clone(),
+            triangle_range: regions[1].triangle_range.clone(),
+        };
+        assert_eq!(
+            builder.remove_region(&mismatched),
+            Err(RegionError::VertexInUse(builder.indicies[0][0]))
+        );
+        assert_eq!(builder.remove_region(&regions[1]), Ok(()));
+        assert_eq!(builder.indicies.len(), 12);
+    }
+}
@@ -0,0 +1,231 @@
+//! The bevy integration for building the [`TriMesh`]es.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "diagnostics")]
+use bevy::{diagnostic::Diagnostics, utils::Instant};
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, VertexAttributeValues},
+};
+
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::{measure_build, measure_frame};
+use crate::{
+    build_merged_trimeshes, invalidate_trimesh_cache, trimesh_from_mesh, TriMesh, TriMeshCache,
+};
+#[cfg(doc)]
+use crate::{GenerateMergedTriMesh, TriMeshes};
+
+/// The [`TriMesh`] built from the [`Mesh`] of an entity.
+#[derive(Component, Deref, DerefMut)]
+pub struct TriMeshComponent(pub TriMesh);
+
+/// The queue of the pending [`TriMesh`] builds, processed a few at a time
+/// to spread the build cost across frames.
+///
+/// Each frame, up to [`Self::builds_per_frame`] jobs are built and the
+/// resulting [`TriMeshComponent`] is inserted on their entities.
+/// The jobs with the [`Mesh`] assets not loaded yet are kept in the queue,
+/// and the jobs of the despawned entities are dropped.
+#[derive(Resource)]
+pub struct TriMeshBuildQueue {
+    /// The maximum amount of the [`TriMesh`]es to build per frame.
+    pub builds_per_frame: usize,
+    /// The pending jobs.
+    pending: VecDeque<(Entity, Handle<Mesh>)>,
+}
+
+impl Default for TriMeshBuildQueue {
+    fn default() -> Self {
+        Self {
+            builds_per_frame: 4,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl TriMeshBuildQueue {
+    /// Enqueue building the [`TriMesh`] from the `mesh` for the `entity`.
+    pub fn push(&mut self, entity: Entity, mesh: Handle<Mesh>) {
+        self.pending.push_back((entity, mesh));
+    }
+
+    /// The amount of the pending jobs.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether there are no pending jobs.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Sent when building the [`TriMesh`] for an entity had to convert
+/// the [`Mesh`] geometry, rather than copy it over as is.
+///
+/// Useful to find the assets that could be exported in a better format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowTriMeshConversion {
+    /// The entity the [`TriMesh`] was built for.
+    pub entity: Entity,
+    /// Why the geometry had to be converted.
+    pub reason: SlowConversionReason,
+}
+
+/// Why the [`Mesh`] geometry had to be converted to build a [`TriMesh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowConversionReason {
+    /// The vertex positions are not `[f32; 3]`.
+    VertexFormat,
+    /// The indicies are not `u32`.
+    IndexFormat,
+    /// The mesh has no indicies.
+    NonIndexed,
+}
+
+impl SlowConversionReason {
+    /// Why the geometry of the [`Mesh`] has to be converted, if it has to.
+    pub fn of(mesh: &Mesh) -> Option<Self> {
+        match (mesh.attribute(Mesh::ATTRIBUTE_POSITION), mesh.indices()) {
+            (Some(VertexAttributeValues::Float32x3(_)), Some(Indices::U32(_))) => None,
+            (Some(VertexAttributeValues::Float32x3(_)), Some(_)) => Some(Self::IndexFormat),
+            (Some(VertexAttributeValues::Float32x3(_)), None) => Some(Self::NonIndexed),
+            _ => Some(Self::VertexFormat),
+        }
+    }
+}
+
+/// Build the [`TriMesh`]es for the jobs from the [`TriMeshBuildQueue`].
+///
+/// Sends a [`SlowTriMeshConversion`] for each [`TriMesh`] built from
+/// the geometry that had to be converted.
+pub fn build_queued_trimeshes(
+    mut commands: Commands,
+    mut queue: ResMut<TriMeshBuildQueue>,
+    meshes: Res<Assets<Mesh>>,
+    mut slow_conversions: EventWriter<SlowTriMeshConversion>,
+    #[cfg(feature = "diagnostics")] mut diagnostics: Option<ResMut<Diagnostics>>,
+) {
+    let mut builds = 0;
+    // Each job is looked at at most once per frame, even if it is re-queued.
+    for _ in 0..queue.pending.len() {
+        if builds >= queue.builds_per_frame {
+            break;
+        }
+        let Some((entity, handle)) = queue.pending.pop_front() else {
+            break;
+        };
+        let Some(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+        let Some(mesh) = meshes.get(&handle) else {
+            queue.pending.push_back((entity, handle));
+            continue;
+        };
+        builds += 1;
+        #[cfg(feature = "diagnostics")]
+        let started = Instant::now();
+        match trimesh_from_mesh(mesh) {
+            Ok(trimesh) => {
+                #[cfg(feature = "diagnostics")]
+                if let Some(diagnostics) = diagnostics.as_deref_mut() {
+                    measure_build(diagnostics, started.elapsed(), trimesh.num_triangles());
+                }
+                if let Some(reason) = SlowConversionReason::of(mesh) {
+                    slow_conversions.send(SlowTriMeshConversion { entity, reason });
+                }
+                entity_commands.insert(TriMeshComponent(trimesh));
+            }
+            Err(error) => warn!("unable to build the trimesh for {entity:?}: {error}"),
+        }
+    }
+
+    #[cfg(feature = "diagnostics")]
+    if let Some(diagnostics) = diagnostics.as_deref_mut() {
+        measure_frame(diagnostics, builds);
+    }
+}
+
+/// Sets up the [`TriMeshBuildQueue`] and the [`GenerateMergedTriMesh`]
+/// processing, and the [`TriMeshCache`] for the [`TriMeshes`].
+#[derive(Debug, Default)]
+pub struct TriMeshPlugin;
+
+impl Plugin for TriMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TriMeshBuildQueue>()
+            .init_resource::<TriMeshCache>()
+            .add_event::<SlowTriMeshConversion>()
+            .add_system(build_queued_trimeshes)
+            .add_system(build_merged_trimeshes)
+            .add_system(invalidate_trimesh_cache);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_plugin(TriMeshPlugin);
+        app
+    }
+
+    #[test]
+    fn drops_jobs_of_despawned_entities() {
+        let mut app = app();
+        let mesh = app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Mesh::from(shape::Cube { size: 1.0 }));
+        let despawned = app.world.spawn_empty().id();
+        let kept = app.world.spawn_empty().id();
+        let mut queue = app.world.resource_mut::<TriMeshBuildQueue>();
+        queue.push(despawned, mesh.clone());
+        queue.push(kept, mesh);
+        app.world.despawn(despawned);
+
+        app.update();
+
+        assert!(app.world.resource::<TriMeshBuildQueue>().is_empty());
+        assert!(app.world.get::<TriMeshComponent>(kept).is_some());
+    }
+
+    #[test]
+    fn reports_the_slow_conversions() {
+        let mut app = app();
+        let mut narrow = Mesh::from(shape::Cube { size: 1.0 });
+        let Some(Indices::U32(indicies)) = narrow.indices() else {
+            unreachable!();
+        };
+        let indicies = indicies.iter().map(|&index| index as u16).collect();
+        narrow.set_indices(Some(Indices::U16(indicies)));
+        let mut meshes = app.world.resource_mut::<Assets<Mesh>>();
+        let narrow = meshes.add(narrow);
+        let wide = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
+        let slow = app.world.spawn_empty().id();
+        let fast = app.world.spawn_empty().id();
+        let mut queue = app.world.resource_mut::<TriMeshBuildQueue>();
+        queue.push(slow, narrow);
+        queue.push(fast, wide);
+
+        app.update();
+
+        let events = app.world.resource::<Events<SlowTriMeshConversion>>();
+        let sent: Vec<_> = events.get_reader().iter(events).cloned().collect();
+        assert_eq!(
+            sent,
+            [SlowTriMeshConversion {
+                entity: slow,
+                reason: SlowConversionReason::IndexFormat,
+            }]
+        );
+        assert!(app.world.get::<TriMeshComponent>(fast).is_some());
+    }
+}
@@ -0,0 +1,76 @@
+//! The [`TriMeshFlags`] for the common use cases.
+
+use crate::{CachedTriMeshBuilder, TriMesh, TriMeshBuildError, TriMeshFlags};
+
+/// A sensible [`TriMeshFlags`] combination for a use case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriMeshPreset {
+    /// A static collider: the duplicate verticies are merged, and
+    /// the degenerate and the duplicate triangles are removed.
+    StaticCollider,
+    /// A collider for the queries that need to know the inside from
+    /// the outside, like the point containment: on top of
+    /// the [`TriMeshPreset::StaticCollider`] cleanup, the topology and
+    /// the pseudo-normals are computed, and the triangles breaking
+    /// the topology are removed.
+    OrientedQuery,
+    /// The fastest build, with the geometry used as is.
+    FastBuild,
+}
+
+impl TriMeshPreset {
+    /// The [`TriMeshFlags`] of the preset.
+    pub fn flags(self) -> TriMeshFlags {
+        let cleanup = TriMeshFlags::MERGE_DUPLICATE_VERTICES
+            | TriMeshFlags::DELETE_DEGENERATE_TRIANGLES
+            | TriMeshFlags::DELETE_DUPLICATE_TRIANGLES;
+        match self {
+            Self::StaticCollider => cleanup,
+            Self::OrientedQuery => {
+                cleanup
+                    | TriMeshFlags::HALF_EDGE_TOPOLOGY
+                    | TriMeshFlags::DELETE_BAD_TOPOLOGY_TRIANGLES
+                    | TriMeshFlags::ORIENTED
+            }
+            Self::FastBuild => TriMeshFlags::empty(),
+        }
+    }
+}
+
+impl CachedTriMeshBuilder {
+    /// Build a new [`TriMesh`] from the precomputed geometry with
+    /// the [`TriMeshFlags`] of the given [`TriMeshPreset`].
+    ///
+    /// To be used multiple times to leverage the cached data.
+    pub fn build_preset(&self, preset: TriMeshPreset) -> Result<TriMesh, TriMeshBuildError> {
+        self.build_with_flags(preset.flags())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use parry3d::{math::Point, query::PointQuery};
+
+    use super::*;
+
+    #[test]
+    fn every_preset_builds_a_cube() {
+        let builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        for (preset, verticies) in [
+            (TriMeshPreset::StaticCollider, 8),
+            (TriMeshPreset::OrientedQuery, 8),
+            (TriMeshPreset::FastBuild, 24),
+        ] {
+            let trimesh = builder.build_preset(preset).unwrap();
+            assert_eq!(trimesh.vertices().len(), verticies, "{preset:?}");
+            assert_eq!(trimesh.indices().len(), 12, "{preset:?}");
+            assert_eq!(trimesh.local_aabb().maxs, Point::new(1.0, 1.0, 1.0));
+        }
+
+        let oriented = builder.build_preset(TriMeshPreset::OrientedQuery).unwrap();
+        assert!(oriented.topology().is_some());
+        assert!(oriented.contains_local_point(&Point::origin()));
+        assert!(!oriented.contains_local_point(&Point::new(2.0, 0.0, 0.0)));
+    }
+}
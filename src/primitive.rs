@@ -0,0 +1,165 @@
+//! Detecting the [`Mesh`]es that approximate the analytic shapes.
+
+use bevy::prelude::*;
+use parry3d::{
+    bounding_volume::Aabb,
+    math::{Isometry, Point, Real, Vector},
+    shape::SharedShape,
+};
+
+#[cfg(doc)]
+use crate::TriMesh;
+use crate::{collect_geometry, topology::signed_volume, TriMeshBuildError};
+
+/// Detect whether the [`Mesh`] approximates a box, a sphere or a capsule
+/// within `tolerance`, and return the matching analytic shape if it does.
+///
+/// The analytic shapes are much cheaper to collide than a [`TriMesh`].
+/// Only the boxes and the capsules aligned with the mesh axes are detected.
+/// The shape is placed in the mesh space, so the boxes and the spheres that
+/// are not centered at the origin are wrapped in a single-part compound.
+/// The flat meshes, like planes, that are at most `tolerance` thick along
+/// any axis enclose no volume, so they are never detected.
+pub fn detect_primitive(
+    mesh: &Mesh,
+    tolerance: Real,
+) -> Result<Option<SharedShape>, TriMeshBuildError> {
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    let aabb = Aabb::from_points(&verticies);
+    if !aabb
+        .mins
+        .iter()
+        .chain(aabb.maxs.iter())
+        .all(|c| c.is_finite())
+    {
+        return Ok(None);
+    }
+    let center = aabb.center();
+    let half_extents = aabb.half_extents();
+    if half_extents.min() <= tolerance {
+        return Ok(None);
+    }
+    let triangles: Vec<[Point<Real>; 3]> = indicies
+        .iter()
+        .map(|triangle| triangle.map(|index| verticies[index as usize]))
+        .collect();
+
+    // All the triangles lie on the box faces, and fill it in.
+    let on_face = |[a, b, c]: &[Point<Real>; 3], axis: usize| {
+        [aabb.mins[axis], aabb.maxs[axis]].into_iter().any(|face| {
+            [a, b, c]
+                .into_iter()
+                .all(|point| (point[axis] - face).abs() <= tolerance)
+        })
+    };
+    let is_box = triangles
+        .iter()
+        .all(|triangle| (0..3).any(|axis| on_face(triangle, axis)));
+    if is_box {
+        let volume = signed_volume(&verticies, &indicies).abs();
+        let extents = aabb.extents();
+        let area = 2.0 * (extents.x * extents.y + extents.y * extents.z + extents.z * extents.x);
+        if (volume - aabb.volume()).abs() <= tolerance * area {
+            return Ok(Some(placed(
+                center,
+                SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z),
+            )));
+        }
+    }
+
+    // All the verticies and the triangle centroids are at the radius from
+    // the core, that is a point for a sphere and a segment for a capsule.
+    let fits = |core: &dyn Fn(&Point<Real>) -> Real, radius: Real| {
+        let centroids = triangles
+            .iter()
+            .map(|[a, b, c]| Point::from((a.coords + b.coords + c.coords) / 3.0));
+        verticies
+            .iter()
+            .copied()
+            .chain(centroids)
+            .all(|point| (core(&point) - radius).abs() <= tolerance)
+    };
+
+    let axis = half_extents.imax();
+    let [radius, other] = [1, 2].map(|offset| half_extents[(axis + offset) % 3]);
+    if (radius - other).abs() > tolerance {
+        return Ok(None);
+    }
+    if (half_extents[axis] - radius).abs() <= tolerance {
+        if fits(&|point| parry3d::na::distance(point, &center), radius) {
+            return Ok(Some(placed(center, SharedShape::ball(radius))));
+        }
+        return Ok(None);
+    }
+
+    let mut half_segment = Vector::zeros();
+    half_segment[axis] = half_extents[axis] - radius;
+    let (a, b) = (center - half_segment, center + half_segment);
+    let segment_distance = |point: &Point<Real>| {
+        let along = (point[axis] - center[axis]).clamp(-half_segment[axis], half_segment[axis]);
+        let mut closest = center;
+        closest[axis] += along;
+        parry3d::na::distance(point, &closest)
+    };
+    if fits(&segment_distance, radius) {
+        return Ok(Some(SharedShape::capsule(a, b, radius)));
+    }
+    Ok(None)
+}
+
+/// Place the `shape` centered at the origin at the `center`.
+fn placed(center: Point<Real>, shape: SharedShape) -> SharedShape {
+    if center == Point::origin() {
+        return shape;
+    }
+    SharedShape::compound(vec![(
+        Isometry::translation(center.x, center.y, center.z),
+        shape,
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uv_sphere_is_a_ball() {
+        let sphere = Mesh::from(shape::UVSphere {
+            radius: 2.0,
+            ..default()
+        });
+        let shape = detect_primitive(&sphere, 0.05).unwrap().expect("a ball");
+        let ball = shape.as_ball().expect("a ball");
+        assert!((ball.radius - 2.0).abs() < 0.05, "{}", ball.radius);
+
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        let shape = detect_primitive(&cube, 1e-4).unwrap().expect("a cuboid");
+        let cuboid = shape.as_cuboid().expect("a cuboid");
+        assert_eq!(cuboid.half_extents, Vector::repeat(1.0));
+
+        let torus = Mesh::from(shape::Torus::default());
+        assert!(detect_primitive(&torus, 0.05).unwrap().is_none());
+    }
+
+    #[test]
+    fn capsule_is_a_capsule_and_a_plane_is_nothing() {
+        let capsule = Mesh::from(shape::Capsule {
+            radius: 0.5,
+            depth: 1.0,
+            ..default()
+        });
+        let shape = detect_primitive(&capsule, 0.05)
+            .unwrap()
+            .expect("a capsule");
+        let capsule = shape.as_capsule().expect("a capsule");
+        assert!((capsule.radius - 0.5).abs() < 0.05, "{}", capsule.radius);
+        assert!(
+            (capsule.height() - 1.0).abs() < 0.05,
+            "{}",
+            capsule.height()
+        );
+
+        let plane = Mesh::from(shape::Plane::from_size(2.0));
+        assert!(detect_primitive(&plane, 1e-4).unwrap().is_none());
+    }
+}
@@ -0,0 +1,374 @@
+//! Spatial queries against the [`TriMesh`] built from a [`Mesh`].
+
+use bevy::prelude::*;
+use parry3d::{
+    math::{Isometry, Real},
+    partitioning::Qbvh,
+    query::{intersection_test, PointQueryWithLocation, Ray, RayCast},
+    shape::{FeatureId, Triangle},
+};
+
+use crate::{
+    collect_geometry,
+    interop::{point_to_vec3, vec3_to_point, vec3_to_vector},
+    topology::triangle_edges,
+    trimesh_from_mesh, TriMesh, TriMeshBuildError,
+};
+
+/// Find the first point where the segment from `from` to `to` hits
+/// the [`TriMesh`] built from the [`Mesh`].
+///
+/// Returns [`None`] if the segment is not blocked.
+pub fn segment_hit(mesh: &Mesh, from: Vec3, to: Vec3) -> Result<Option<Vec3>, TriMeshBuildError> {
+    let trimesh = trimesh_from_mesh(mesh)?;
+
+    let length = from.distance(to);
+    if length == 0.0 {
+        return Ok(None);
+    }
+    let dir = (to - from) / length;
+    let ray = Ray::new(vec3_to_point(from), vec3_to_vector(dir));
+
+    let hit = trimesh
+        .cast_local_ray(&ray, length, false)
+        .map(|toi| from + dir * toi);
+    Ok(hit)
+}
+
+/// The closest hit of the ray cast by [`pick`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickHit {
+    /// The index of the hit triangle.
+    pub triangle: u32,
+    /// The barycentric coordinates of the hit point within the hit triangle,
+    /// in the order of the triangle verticies.
+    ///
+    /// Use them to interpolate the vertex attributes at the hit point.
+    pub barycentric: [f32; 3],
+    /// The hit point.
+    pub point: Vec3,
+    /// The distance from the ray origin to the hit point.
+    pub distance: f32,
+}
+
+/// Find the closest triangle hit by the ray cast from `ray_origin` in the
+/// `ray_dir` direction against the [`TriMesh`] built from the [`Mesh`].
+///
+/// The triangles are hit from both sides. Returns [`None`] if nothing is hit,
+/// or if `ray_dir` is zero.
+pub fn pick(
+    mesh: &Mesh,
+    ray_origin: Vec3,
+    ray_dir: Vec3,
+) -> Result<Option<PickHit>, TriMeshBuildError> {
+    let trimesh = trimesh_from_mesh(mesh)?;
+
+    let Some(dir) = ray_dir.try_normalize() else {
+        return Ok(None);
+    };
+    let ray = Ray::new(vec3_to_point(ray_origin), vec3_to_vector(dir));
+    let Some(intersection) = trimesh.cast_local_ray_and_get_normal(&ray, Real::MAX, false) else {
+        return Ok(None);
+    };
+
+    let Some(MeshFeature::Face(triangle)) = resolve_feature(&trimesh, intersection.feature) else {
+        return Ok(None);
+    };
+    let point = ray_origin + dir * intersection.toi;
+    let corners = trimesh.indices()[triangle as usize]
+        .map(|vertex| point_to_vec3(trimesh.vertices()[vertex as usize]));
+
+    Ok(Some(PickHit {
+        triangle,
+        barycentric: barycentric(corners, point),
+        point,
+        distance: intersection.toi,
+    }))
+}
+
+/// Compute the barycentric coordinates of the `point` lying within
+/// the triangle.
+fn barycentric([a, b, c]: [Vec3; 3], point: Vec3) -> [f32; 3] {
+    let (ab, ac, ap) = (b - a, c - a, point - a);
+    let (d00, d01, d11) = (ab.dot(ab), ab.dot(ac), ac.dot(ac));
+    let (d20, d21) = (ap.dot(ab), ap.dot(ac));
+    let denom = d00 * d11 - d01 * d01;
+    let v = (d11 * d20 - d01 * d21) / denom;
+    let w = (d00 * d21 - d01 * d20) / denom;
+    [1.0 - v - w, v, w]
+}
+
+/// A concrete element of the [`TriMesh`], resolved from a [`FeatureId`] by
+/// [`resolve_feature`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFeature {
+    /// The triangle with the given index.
+    Face(u32),
+    /// The edge between the given verticies, directed as in its triangle.
+    Edge([u32; 2]),
+    /// The vertex with the given index.
+    Vertex(u32),
+}
+
+/// Resolve the [`FeatureId`] reported by the `parry3d` queries against
+/// the whole [`TriMesh`].
+///
+/// A [`TriMesh`] only reports the faces, with the ids being the triangle
+/// indicies, and the backfaces counted past the amount of triangles.
+/// The edges and the verticies are only reported by the queries against
+/// a single [`Triangle`], use [`resolve_triangle_feature`] for them.
+///
+/// Returns [`None`] for the other features and for the ids out of
+/// the [`TriMesh`] bounds.
+pub fn resolve_feature(trimesh: &TriMesh, feature: FeatureId) -> Option<MeshFeature> {
+    let triangles = trimesh.indices().len();
+    match feature {
+        FeatureId::Face(id) if (id as usize) < 2 * triangles => {
+            Some(MeshFeature::Face(id % triangles as u32))
+        }
+        _ => None,
+    }
+}
+
+/// Resolve the [`FeatureId`] reported by the `parry3d` queries against
+/// the [`Triangle`] with the given index in the [`TriMesh`].
+///
+/// The features of a [`Triangle`] are local to it: its front and back
+/// faces are `0` and `1`, its verticies are `0` to `2`, and its edges are
+/// `0` to `2`, starting from the one between the first two verticies.
+///
+/// Returns [`None`] for the [`FeatureId::Unknown`] and for the ids out of
+/// the [`Triangle`] or the [`TriMesh`] bounds.
+pub fn resolve_triangle_feature(
+    trimesh: &TriMesh,
+    triangle: u32,
+    feature: FeatureId,
+) -> Option<MeshFeature> {
+    let indicies = *trimesh.indices().get(triangle as usize)?;
+    match feature {
+        FeatureId::Face(0 | 1) => Some(MeshFeature::Face(triangle)),
+        FeatureId::Edge(edge) => {
+            let &(from, to) = triangle_edges(indicies).get(edge as usize)?;
+            Some(MeshFeature::Edge([from, to]))
+        }
+        FeatureId::Vertex(vertex) => indicies
+            .get(vertex as usize)
+            .map(|&vertex| MeshFeature::Vertex(vertex)),
+        _ => None,
+    }
+}
+
+/// Check whether the segment from `from` to `to` is blocked by
+/// the [`TriMesh`] built from the [`Mesh`].
+pub fn segment_blocked(mesh: &Mesh, from: Vec3, to: Vec3) -> Result<bool, TriMeshBuildError> {
+    Ok(segment_hit(mesh, from, to)?.is_some())
+}
+
+/// Check whether the [`TriMesh`]es built from the [`Mesh`]es placed at
+/// `iso_a` and `iso_b` intersect.
+///
+/// Only the surfaces are tested, so a mesh lying entirely within the other
+/// mesh does not intersect it.
+pub fn meshes_intersect(
+    a: &Mesh,
+    b: &Mesh,
+    iso_a: &Isometry<Real>,
+    iso_b: &Isometry<Real>,
+) -> Result<bool, TriMeshBuildError> {
+    let a = trimesh_from_mesh(a)?;
+    let b = trimesh_from_mesh(b)?;
+    // The composite shapes like the `TriMesh` are always supported.
+    Ok(intersection_test(iso_a, &a, iso_b, &b).unwrap_or(false))
+}
+
+/// The signed distance from the point to the surface of the [`TriMesh`],
+/// negative inside of it.
+///
+/// The sign is only reliable for the [`TriMesh`]es with the pseudo-normals,
+/// see [`crate::CachedTriMeshBuilder::build_with_pseudo_normals`].
+pub fn signed_distance(mesh: &TriMesh, point: Vec3) -> Real {
+    let point = vec3_to_point(point);
+    let (projection, _) = mesh.project_local_point_and_get_location(&point, false);
+    let distance = (projection.point - point).norm();
+    if projection.is_inside {
+        -distance
+    } else {
+        distance
+    }
+}
+
+/// Build the [`Qbvh`] acceleration structure over the triangles of
+/// the [`Mesh`], without building the whole [`TriMesh`].
+///
+/// The leaf data is the triangle index.
+pub fn qbvh_from_mesh(mesh: &Mesh) -> Result<Qbvh<u32>, TriMeshBuildError> {
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    let leaves = indicies.iter().enumerate().map(|(index, triangle)| {
+        let [a, b, c] = triangle.map(|vertex| verticies[vertex as usize]);
+        (index as u32, Triangle::new(a, b, c).local_aabb())
+    });
+
+    let mut qbvh = Qbvh::new();
+    qbvh.clear_and_rebuild(leaves, 0.0);
+    Ok(qbvh)
+}
+
+#[cfg(test)]
+mod tests {
+    use parry3d::{
+        bounding_volume::BoundingVolume,
+        math::{Point, Vector},
+    };
+
+    use super::*;
+    use crate::CachedTriMeshBuilder;
+
+    fn cube() -> TriMesh {
+        trimesh_from_mesh(&Mesh::from(shape::Cube { size: 2.0 })).unwrap()
+    }
+
+    #[test]
+    fn resolves_ray_hit_faces() {
+        let trimesh = cube();
+        let ray = Ray::new(Point::new(0.3, 0.2, -5.0), Vector::z());
+        let hit = trimesh
+            .cast_local_ray_and_get_normal(&ray, Real::MAX, false)
+            .unwrap();
+        let Some(MeshFeature::Face(triangle)) = resolve_feature(&trimesh, hit.feature) else {
+            panic!("expected a face, got {:?}", hit.feature);
+        };
+        let corners =
+            trimesh.indices()[triangle as usize].map(|vertex| trimesh.vertices()[vertex as usize]);
+        assert!(corners.iter().all(|corner| corner.z == -1.0));
+
+        // The backface of the same triangle, hit from the inside.
+        let ray = Ray::new(Point::new(0.3, 0.2, 0.0), -Vector::z());
+        let hit = trimesh
+            .cast_local_ray_and_get_normal(&ray, Real::MAX, false)
+            .unwrap();
+        assert_eq!(
+            resolve_feature(&trimesh, hit.feature),
+            Some(MeshFeature::Face(triangle))
+        );
+
+        assert_eq!(resolve_feature(&trimesh, FeatureId::Vertex(0)), None);
+        assert_eq!(resolve_feature(&trimesh, FeatureId::Face(24)), None);
+    }
+
+    #[test]
+    fn resolves_triangle_local_features() {
+        let trimesh = cube();
+        let triangle = 5;
+        let indicies = trimesh.indices()[triangle as usize];
+        assert_eq!(
+            resolve_triangle_feature(&trimesh, triangle, FeatureId::Vertex(1)),
+            Some(MeshFeature::Vertex(indicies[1]))
+        );
+        assert_eq!(
+            resolve_triangle_feature(&trimesh, triangle, FeatureId::Edge(2)),
+            Some(MeshFeature::Edge([indicies[2], indicies[0]]))
+        );
+        assert_eq!(
+            resolve_triangle_feature(&trimesh, triangle, FeatureId::Face(1)),
+            Some(MeshFeature::Face(triangle))
+        );
+        assert_eq!(
+            resolve_triangle_feature(&trimesh, triangle, FeatureId::Vertex(3)),
+            None
+        );
+        assert_eq!(
+            resolve_triangle_feature(&trimesh, 12, FeatureId::Vertex(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn walls_block_the_segments_through_them() {
+        let wall = Mesh::from(shape::Box::new(0.2, 4.0, 4.0));
+        let blocked = |from, to| segment_blocked(&wall, from, to).unwrap();
+        assert!(blocked(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(5.0, 0.0, 0.0)));
+        assert!(blocked(
+            Vec3::new(5.0, 1.0, 1.0),
+            Vec3::new(-5.0, -1.0, -1.0)
+        ));
+        assert!(!blocked(
+            Vec3::new(-5.0, 3.0, 0.0),
+            Vec3::new(5.0, 3.0, 0.0)
+        ));
+        assert!(!blocked(
+            Vec3::new(-5.0, 0.0, 0.0),
+            Vec3::new(-1.0, 0.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn qbvh_root_bounds_every_triangle() {
+        let sphere = Mesh::try_from(shape::Icosphere {
+            radius: 3.0,
+            subdivisions: 2,
+        })
+        .unwrap();
+        let qbvh = qbvh_from_mesh(&sphere).unwrap();
+        let root = qbvh.root_aabb();
+        let trimesh = trimesh_from_mesh(&sphere).unwrap();
+        assert_eq!(qbvh.raw_proxies().len(), trimesh.indices().len());
+        for triangle in trimesh.triangles() {
+            let aabb = triangle.local_aabb();
+            assert!(root.contains(&aabb), "{aabb:?} is outside of {root:?}");
+        }
+        assert_eq!(root, trimesh.local_aabb());
+    }
+
+    #[test]
+    fn pick_interpolates_the_hit_point() {
+        let cube = Mesh::from(shape::Cube { size: 2.0 });
+        let hit = pick(&cube, Vec3::new(0.3, 0.2, -5.0), Vec3::Z)
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.point, Vec3::new(0.3, 0.2, -1.0));
+        assert_eq!(hit.distance, 4.0);
+        let sum: f32 = hit.barycentric.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-6, "{:?}", hit.barycentric);
+        assert!(hit.barycentric.iter().all(|&weight| weight >= 0.0));
+
+        let trimesh = trimesh_from_mesh(&cube).unwrap();
+        let corners = trimesh.indices()[hit.triangle as usize]
+            .map(|vertex| point_to_vec3(trimesh.vertices()[vertex as usize]));
+        let interpolated: Vec3 = corners
+            .iter()
+            .zip(hit.barycentric)
+            .map(|(&corner, weight)| corner * weight)
+            .sum();
+        assert!(interpolated.abs_diff_eq(hit.point, 1e-6), "{interpolated}");
+
+        assert_eq!(pick(&cube, Vec3::new(3.0, 0.0, -5.0), Vec3::Z), Ok(None));
+    }
+
+    #[test]
+    fn tells_the_overlapping_cubes_from_the_separated_ones() {
+        let cube = Mesh::from(shape::Cube { size: 2.0 });
+        let intersect = |x| {
+            meshes_intersect(
+                &cube,
+                &cube,
+                &Isometry::identity(),
+                &Isometry::translation(x, 0.5, 0.0),
+            )
+            .unwrap()
+        };
+        assert!(intersect(1.5));
+        assert!(!intersect(2.5));
+    }
+
+    #[test]
+    fn signed_distance_is_negative_inside() {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        builder.weld(1e-4);
+        let trimesh = builder.build_with_pseudo_normals().unwrap();
+
+        let inside = signed_distance(&trimesh, Vec3::new(0.2, 0.5, 0.0));
+        assert!((inside + 0.5).abs() < 1e-6, "{inside}");
+        let outside = signed_distance(&trimesh, Vec3::new(3.0, 0.0, 0.0));
+        assert!((outside - 2.0).abs() < 1e-6, "{outside}");
+    }
+}
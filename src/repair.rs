@@ -0,0 +1,123 @@
+//! Repairing the messy [`Mesh`] geometry in one go.
+
+use bevy::prelude::*;
+
+use crate::{CachedTriMeshBuilder, TriMeshBuildError};
+
+/// What [`CachedTriMeshBuilder::from_mesh_repaired`] has fixed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The amount of the removed degenerate triangles.
+    pub degenerate_triangles: usize,
+    /// The amount of the verticies merged into the coincident ones.
+    pub welded_verticies: usize,
+    /// The amount of the removed verticies not used by any triangle.
+    pub unused_verticies: usize,
+    /// The amount of the removed duplicate triangles.
+    pub duplicate_triangles: usize,
+}
+
+impl RepairReport {
+    /// Check whether nothing had to be fixed.
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl CachedTriMeshBuilder {
+    /// Extract the geometry from a [`Mesh`] like [`Self::from_mesh`] does,
+    /// and repair it.
+    ///
+    /// The coincident verticies are welded, then the degenerate and
+    /// the duplicate triangles, wound the same way, and the unused verticies
+    /// are removed.
+    ///
+    /// Fails with [`TriMeshBuildError::EmptyGeometry`] if no triangles are
+    /// left.
+    pub fn from_mesh_repaired(mesh: &Mesh) -> Result<(Self, RepairReport), TriMeshBuildError> {
+        let mut builder = Self::from_mesh(mesh)?;
+        let mut report = RepairReport::default();
+
+        let verticies = builder.verticies.len();
+        builder.dedup_exact();
+        report.welded_verticies = verticies - builder.verticies.len();
+
+        report.degenerate_triangles = builder.remove_degenerate_triangles();
+
+        let triangles = builder.indicies.len();
+        builder.dedup_triangles(false);
+        report.duplicate_triangles = triangles - builder.indicies.len();
+
+        let verticies = builder.verticies.len();
+        builder.prune_unused_vertices();
+        report.unused_verticies = verticies - builder.verticies.len();
+
+        if builder.indicies.is_empty() {
+            return Err(TriMeshBuildError::EmptyGeometry);
+        }
+        Ok((builder, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+    use parry3d::math::Point;
+
+    use super::*;
+
+    #[test]
+    fn repairs_a_messy_mesh() {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [5.0, 5.0, 5.0],
+                [0.0, 0.0, 1.0],
+            ],
+        );
+        // A duplicate of the first triangle through the coincident vertex,
+        // a degenerate triangle, and an unused vertex.
+        mesh.set_indices(Some(Indices::U32(vec![0, 1, 2, 0, 3, 2, 0, 0, 5, 1, 0, 5])));
+
+        let (builder, report) = CachedTriMeshBuilder::from_mesh_repaired(&mesh).unwrap();
+        assert_eq!(
+            report,
+            RepairReport {
+                degenerate_triangles: 1,
+                welded_verticies: 1,
+                unused_verticies: 1,
+                duplicate_triangles: 1,
+            }
+        );
+        assert!(!report.is_clean());
+        assert_eq!(
+            builder.verticies,
+            [
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(0.0, 0.0, 1.0),
+            ]
+        );
+        assert_eq!(builder.indicies, [[0, 1, 2], [1, 0, 3]]);
+
+        // Repairing the repaired geometry again has nothing left to fix.
+        let mut repaired = Mesh::new(PrimitiveTopology::TriangleList);
+        let verticies: Vec<[f32; 3]> = builder
+            .verticies
+            .iter()
+            .map(|&vertex| vertex.into())
+            .collect();
+        repaired.insert_attribute(Mesh::ATTRIBUTE_POSITION, verticies);
+        repaired.set_indices(Some(Indices::U32(
+            builder.indicies.iter().flatten().copied().collect(),
+        )));
+        let (_, report) = CachedTriMeshBuilder::from_mesh_repaired(&repaired).unwrap();
+        assert!(report.is_clean());
+    }
+}
@@ -0,0 +1,135 @@
+//! Building the [`TriMesh`] for a pose of a skinned [`Mesh`].
+
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+
+use crate::{
+    convert_verticies, interop::point_to_vec3, new_trimesh,
+    prepare_trimesh_from_mesh_with_positions, ExtractGeometryError, TriMesh, TriMeshBuildError,
+};
+
+/// Create a [`TriMesh`] from the [`Mesh`] geometry skinned on the CPU with
+/// the given `joint_matrices`.
+///
+/// The `joint_matrices` are the joint transforms multiplied by the inverse
+/// bindposes, as used by the GPU skinning, and are indexed by
+/// the [`Mesh::ATTRIBUTE_JOINT_INDEX`]. Each vertex is moved by the blend of
+/// its joint matrices weighted by the [`Mesh::ATTRIBUTE_JOINT_WEIGHT`].
+pub fn trimesh_from_skinned(
+    mesh: &Mesh,
+    joint_matrices: &[Mat4],
+) -> Result<TriMesh, TriMeshBuildError> {
+    let positions = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .ok_or_else(|| ExtractGeometryError::missing_positions(mesh))?;
+    let Some(VertexAttributeValues::Float32x4(weights)) =
+        mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT)
+    else {
+        return Err(TriMeshBuildError::MissingAttribute(
+            Mesh::ATTRIBUTE_JOINT_WEIGHT.id,
+        ));
+    };
+    let Some(VertexAttributeValues::Uint16x4(joints)) = mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX)
+    else {
+        return Err(TriMeshBuildError::MissingAttribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX.id,
+        ));
+    };
+
+    let expected = positions.len();
+    for (attribute, len) in [
+        (Mesh::ATTRIBUTE_JOINT_WEIGHT.id, weights.len()),
+        (Mesh::ATTRIBUTE_JOINT_INDEX.id, joints.len()),
+    ] {
+        if len != expected {
+            return Err(TriMeshBuildError::InconsistentAttributeLengths {
+                attribute,
+                len,
+                expected,
+            });
+        }
+    }
+    if let Some(&joint) = joints.iter().flatten().max() {
+        let len = joint_matrices.len();
+        if usize::from(joint) >= len {
+            return Err(TriMeshBuildError::JointOutOfBounds {
+                joint: joint.into(),
+                len,
+            });
+        }
+    }
+
+    let verticies =
+        convert_verticies(positions).map_err(TriMeshBuildError::UnsupportedVerexDataFormat)?;
+    let posed: Vec<[f32; 3]> = verticies
+        .zip(weights.iter().zip(joints))
+        .map(|(vertex, (weights, joints))| {
+            let vertex = point_to_vec3(vertex);
+            let posed: Vec3 = weights
+                .iter()
+                .zip(joints)
+                .map(|(&weight, &joint)| {
+                    weight * joint_matrices[usize::from(joint)].transform_point3(vertex)
+                })
+                .sum();
+            posed.to_array()
+        })
+        .collect();
+
+    let posed = VertexAttributeValues::Float32x3(posed);
+    let (verticies, indicies) = prepare_trimesh_from_mesh_with_positions(mesh, &posed)?;
+    Ok(new_trimesh(verticies.collect(), indicies.collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+    use parry3d::math::Point;
+
+    use super::*;
+
+    fn skinned_triangle() -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX,
+            VertexAttributeValues::Uint16x4(vec![[0, 0, 0, 0], [1, 0, 0, 0], [0, 1, 0, 0]]),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_WEIGHT,
+            vec![
+                [1.0, 0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0, 0.0],
+                [0.5, 0.5, 0.0, 0.0],
+            ],
+        );
+        mesh.set_indices(Some(Indices::U32(vec![0, 1, 2])));
+        mesh
+    }
+
+    #[test]
+    fn blends_the_two_bones() {
+        let joints = [Mat4::IDENTITY, Mat4::from_translation(Vec3::Y * 2.0)];
+        let trimesh = trimesh_from_skinned(&skinned_triangle(), &joints).unwrap();
+        assert_eq!(
+            trimesh.vertices(),
+            &[
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 2.0, 0.0),
+                Point::new(0.0, 1.0, 1.0),
+            ]
+        );
+        assert_eq!(trimesh.indices(), &[[0, 1, 2]]);
+    }
+
+    #[test]
+    fn rejects_the_missing_joints() {
+        let error = trimesh_from_skinned(&skinned_triangle(), &[Mat4::IDENTITY]).err();
+        assert_eq!(
+            error,
+            Some(TriMeshBuildError::JointOutOfBounds { joint: 1, len: 1 })
+        );
+    }
+}
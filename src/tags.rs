@@ -0,0 +1,68 @@
+//! Per-triangle tags of the cached [`TriMesh`] geometry.
+
+use crate::CachedTriMeshBuilder;
+#[cfg(doc)]
+use crate::TriMesh;
+
+impl CachedTriMeshBuilder {
+    /// The tag of the triangle with the given index, if it is tagged.
+    ///
+    /// The triangle indicies are the same in the built [`TriMesh`], as long as
+    /// it is built without the [`crate::TriMeshFlags`] removing triangles,
+    /// so the tag of a hit triangle can be looked up here.
+    pub fn tag_of(&self, triangle: u32) -> Option<u32> {
+        self.tags.get(triangle as usize).copied()
+    }
+
+    /// Retain only the triangles for which `keep` returns `true`, given
+    /// the triangle index, keeping the tags and the ids aligned with
+    /// the remaining triangles.
+    pub(crate) fn retain_triangles(&mut self, mut keep: impl FnMut(usize, &[u32; 3]) -> bool) {
+        let kept: Vec<bool> = self
+            .indicies
+            .iter()
+            .enumerate()
+            .map(|(triangle, indicies)| keep(triangle, indicies))
+            .collect();
+        let mut flags = kept.iter();
+        self.indicies
+            .retain(|_| flags.next().copied().unwrap_or_default());
+        let mut flags = kept.iter();
+        self.tags
+            .retain(|_| flags.next().copied().unwrap_or_default());
+        let mut flags = kept.iter();
+        self.triangle_ids_mut()
+            .retain(|_| flags.next().copied().unwrap_or_default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parry3d::math::Point;
+
+    use super::*;
+
+    #[test]
+    fn tags_follow_the_remaining_triangles() {
+        let mut builder = CachedTriMeshBuilder::new();
+        builder.verticies = vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(0.0, 0.0, 1.0),
+        ];
+        // The degenerate triangles are the second and the fourth.
+        builder.indicies = vec![[0, 1, 2], [0, 0, 1], [0, 1, 3], [2, 2, 3], [1, 2, 3]];
+        builder.tags = vec![10, 11, 12, 13];
+
+        assert_eq!(builder.remove_degenerate_triangles(), 2);
+        assert_eq!(builder.indicies, [[0, 1, 2], [0, 1, 3], [1, 2, 3]]);
+        assert_eq!(builder.tags, [10, 12]);
+        assert_eq!(
+            (0..3)
+                .map(|triangle| builder.tag_of(triangle))
+                .collect::<Vec<_>>(),
+            [Some(10), Some(12), None]
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Triangle connectivity analysis.
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use parry3d::{
+    math::{Point, Real},
+    shape::Polyline,
+};
+
+use crate::{collect_geometry, prepare_trimesh_from_mesh, CachedTriMeshBuilder, TriMeshBuildError};
+
+/// The undirected edge key, with the smaller vertex index first.
+pub(crate) fn edge_key(a: u32, b: u32) -> [u32; 2] {
+    if a < b {
+        [a, b]
+    } else {
+        [b, a]
+    }
+}
+
+/// The directed edges of a triangle, in the triangle winding order.
+pub(crate) fn triangle_edges([a, b, c]: [u32; 3]) -> [(u32, u32); 3] {
+    [(a, b), (b, c), (c, a)]
+}
+
+/// Map each undirected edge to the triangles that use it.
+///
+/// The triangles are listed in order. Only use the map for lookups, iterating
+/// over it is not deterministic.
+pub(crate) fn edge_map(indicies: &[[u32; 3]]) -> HashMap<[u32; 2], Vec<usize>> {
+    let mut map: HashMap<_, Vec<_>> = HashMap::default();
+    for (triangle, &indicies) in indicies.iter().enumerate() {
+        for (from, to) in triangle_edges(indicies) {
+            map.entry(edge_key(from, to)).or_default().push(triangle);
+        }
+    }
+    map
+}
+
+/// The signed volume enclosed by the given triangles.
+///
+/// Positive when the triangles of a closed surface are wound
+/// counter-clockwise when looking from the outside.
+pub(crate) fn signed_volume<'a>(
+    verticies: &[Point<Real>],
+    triangles: impl IntoIterator<Item = &'a [u32; 3]>,
+) -> Real {
+    triangles
+        .into_iter()
+        .map(|&[a, b, c]| {
+            let [a, b, c] = [a, b, c].map(|index| verticies[index as usize].coords);
+            a.dot(&b.cross(&c))
+        })
+        .sum::<Real>()
+        / 6.0
+}
+
+/// Collect the open edges of the [`Mesh`], that is the edges used by exactly
+/// one triangle.
+///
+/// The edges are directed as in the triangle that uses them, and are listed
+/// in the triangle order.
+pub fn boundary_edges(mesh: &Mesh) -> Result<Vec<[u32; 2]>, TriMeshBuildError> {
+    let (_, indicies) = prepare_trimesh_from_mesh(mesh)?;
+    let indicies: Vec<_> = indicies.collect();
+    Ok(open_edges(&indicies))
+}
+
+/// The edges used by exactly one of the triangles, directed as in
+/// the triangle that uses them, and listed in the triangle order.
+fn open_edges(indicies: &[[u32; 3]]) -> Vec<[u32; 2]> {
+    let edges = edge_map(indicies);
+    indicies
+        .iter()
+        .flat_map(|&triangle| triangle_edges(triangle))
+        .filter(|&(from, to)| edges[&edge_key(from, to)].len() == 1)
+        .map(|(from, to)| [from, to])
+        .collect()
+}
+
+/// Which of the [`Mesh`] edges to select.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeSelection {
+    /// All the edges, each listed once.
+    All,
+    /// Only the open edges, as with [`boundary_edges`].
+    Boundary,
+}
+
+/// Build a [`Polyline`] from the selected edges of the [`Mesh`], like for
+/// the colliders made of the mesh outline only.
+///
+/// The segments are listed in the triangle order, and the polyline keeps
+/// all the mesh verticies. Returns [`TriMeshBuildError::EmptyGeometry`]
+/// if no edges are selected, like for the boundary of a closed mesh.
+pub fn polyline_from_mesh(
+    mesh: &Mesh,
+    edges: EdgeSelection,
+) -> Result<Polyline, TriMeshBuildError> {
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    let segments = match edges {
+        EdgeSelection::All => {
+            let mut seen = HashSet::default();
+            indicies
+                .iter()
+                .flat_map(|&triangle| triangle_edges(triangle))
+                .filter(|&(from, to)| seen.insert(edge_key(from, to)))
+                .map(|(from, to)| [from, to])
+                .collect()
+        }
+        EdgeSelection::Boundary => open_edges(&indicies),
+    };
+    if segments.is_empty() {
+        return Err(TriMeshBuildError::EmptyGeometry);
+    }
+    Ok(Polyline::new(verticies, Some(segments)))
+}
+
+/// Compute the Euler characteristic `V - E + F` of the [`Mesh`] surface.
+///
+/// Only the verticies referenced by the triangles are counted, and the
+/// connectivity is determined by the shared vertex indicies, so meshes with
+/// the duplicated verticies have to be welded first to get
+/// a meaningful result.
+pub fn euler_characteristic(mesh: &Mesh) -> Result<i64, TriMeshBuildError> {
+    let (_, indicies) = prepare_trimesh_from_mesh(mesh)?;
+    let indicies: Vec<_> = indicies.collect();
+
+    let mut verticies: Vec<u32> = indicies.iter().flatten().copied().collect();
+    verticies.sort_unstable();
+    verticies.dedup();
+    let edges = edge_map(&indicies);
+
+    Ok(verticies.len() as i64 - edges.len() as i64 + indicies.len() as i64)
+}
+
+/// Compute the genus of the [`Mesh`] surface, that is the amount of
+/// "handles" it has, from its [`euler_characteristic`].
+///
+/// Only meaningful for closed connected orientable manifolds: a sphere has
+/// a genus of `0`, and a torus has a genus of `1`.
+pub fn genus(mesh: &Mesh) -> Result<i64, TriMeshBuildError> {
+    Ok((2 - euler_characteristic(mesh)?) / 2)
+}
+
+impl CachedTriMeshBuilder {
+    /// Reorient the triangles so that their normals consistently point
+    /// outward, or inward if `outward` is `false`.
+    ///
+    /// The orientation is flood-filled across the adjacent triangles of each
+    /// connected component, and then the whole component is flipped if its
+    /// signed volume disagrees with the requested direction. Adjacency is
+    /// determined by shared vertex indicies, so triangles that only share
+    /// vertex positions are treated as disconnected.
+    ///
+    /// For closed meshes the result is exact. For open meshes this is
+    /// best-effort: the triangles are still made consistent with their
+    /// neighbours, but which side is "outward" is only decided by the signed
+    /// volume of the open surface, and is left as is if that is zero.
+    pub fn normalize_winding(&mut self, outward: bool) {
+        let edges = edge_map(&self.indicies);
+        let mut visited = vec![false; self.indicies.len()];
+        let mut component = Vec::new();
+
+        for seed in 0..self.indicies.len() {
+            if visited[seed] {
+                continue;
+            }
+            visited[seed] = true;
+            component.clear();
+            component.push(seed);
+
+            let mut next = 0;
+            while let Some(&triangle) = component.get(next) {
+                next += 1;
+                for (from, to) in triangle_edges(self.indicies[triangle]) {
+                    for &neighbour in &edges[&edge_key(from, to)] {
+                        if visited[neighbour] {
+                            continue;
+                        }
+                        visited[neighbour] = true;
+                        // A consistently wound neighbour walks the shared
+                        // edge in the opposite direction.
+                        if triangle_edges(self.indicies[neighbour]).contains(&(from, to)) {
+                            self.indicies[neighbour].swap(1, 2);
+                        }
+                        component.push(neighbour);
+                    }
+                }
+            }
+
+            let volume = signed_volume(
+                &self.verticies,
+                component.iter().map(|&triangle| &self.indicies[triangle]),
+            );
+            if volume != 0.0 && (volume > 0.0) != outward {
+                for &triangle in &component {
+                    self.indicies[triangle].swap(1, 2);
+                }
+            }
+        }
+        self.mark_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        prelude::shape,
+        render::{mesh::Indices, render_resource::PrimitiveTopology},
+    };
+
+    use super::*;
+    use crate::geometry::triangle_normal;
+
+    fn mesh(verticies: Vec<[f32; 3]>, indicies: Vec<u32>) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, verticies);
+        mesh.set_indices(Some(Indices::U32(indicies)));
+        mesh
+    }
+
+    /// The [`Mesh`] with the verticies sharing a position merged.
+    fn welded(original: Mesh) -> Mesh {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&original).unwrap();
+        builder.weld(1e-4);
+        let verticies = builder
+            .verticies
+            .iter()
+            .map(|&vertex| vertex.into())
+            .collect();
+        mesh(verticies, builder.indicies.into_iter().flatten().collect())
+    }
+
+    fn welded_cube() -> CachedTriMeshBuilder {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        builder.weld(1e-4);
+        builder
+    }
+
+    /// Whether each triangle of the cube centered at the origin faces away
+    /// from its center.
+    fn faces_outward(builder: &CachedTriMeshBuilder) -> Vec<bool> {
+        builder
+            .indicies
+            .iter()
+            .map(|triangle| {
+                let corners = triangle.map(|index| builder.verticies[index as usize]);
+                let center = (corners[0].coords + corners[1].coords + corners[2].coords) / 3.0;
+                triangle_normal(corners).dot(&center) > 0.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn normalizes_the_winding_of_the_flipped_faces() {
+        let mut builder = welded_cube();
+        for triangle in [0, 3, 4, 9] {
+            builder.indicies[triangle].swap(1, 2);
+        }
+        assert!(faces_outward(&builder).contains(&false));
+
+        builder.normalize_winding(true);
+        assert_eq!(faces_outward(&builder), [true; 12]);
+        builder.normalize_winding(false);
+        assert_eq!(faces_outward(&builder), [false; 12]);
+    }
+
+    #[test]
+    fn single_triangle_is_all_boundary() {
+        let triangle = mesh(
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            vec![0, 1, 2],
+        );
+        assert_eq!(boundary_edges(&triangle), Ok(vec![[0, 1], [1, 2], [2, 0]]));
+        let quad = mesh(
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            vec![0, 1, 2, 0, 2, 3],
+        );
+        assert_eq!(
+            boundary_edges(&quad),
+            Ok(vec![[0, 1], [1, 2], [2, 3], [3, 0]])
+        );
+    }
+
+    #[test]
+    fn euler_characteristic_tells_the_sphere_from_the_torus() {
+        let cube = welded(shape::Cube::new(2.0).into());
+        assert_eq!(euler_characteristic(&cube), Ok(2));
+        assert_eq!(genus(&cube), Ok(0));
+
+        let torus = welded(shape::Torus::default().into());
+        assert_eq!(euler_characteristic(&torus), Ok(0));
+        assert_eq!(genus(&torus), Ok(1));
+    }
+
+    #[test]
+    fn polyline_follows_the_open_boundary() {
+        let plane = Mesh::from(shape::Plane {
+            size: 2.0,
+            subdivisions: 1,
+        });
+        let boundary = polyline_from_mesh(&plane, EdgeSelection::Boundary).unwrap();
+        assert_eq!(boundary.num_segments(), 8);
+        assert!(boundary.segments().all(|segment| [segment.a, segment.b]
+            .iter()
+            .all(|point| { point.x.abs() == 1.0 || point.z.abs() == 1.0 })));
+        let all = polyline_from_mesh(&plane, EdgeSelection::All).unwrap();
+        assert_eq!(all.num_segments(), 16);
+
+        let closed = welded(shape::Cube::new(2.0).into());
+        assert_eq!(
+            polyline_from_mesh(&closed, EdgeSelection::Boundary).err(),
+            Some(TriMeshBuildError::EmptyGeometry)
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Thorough [`Mesh`] validation.
+
+use bevy::prelude::*;
+use parry3d::{
+    math::{Isometry, Point, Real},
+    na,
+    partitioning::Qbvh,
+    query::intersection_test,
+    shape::{Segment, Shape, Triangle},
+};
+
+use crate::{can_convert, collect_geometry, vertex_count, CachedTriMeshBuilder, TriMeshBuildError};
+
+/// Check the [`Mesh`] for the malformed geometry.
+///
+/// Delegates to [`can_convert`] for the conversion checks, like the index
+/// bounds, and on top of that makes sure that all the vertex attributes
+/// have the same length as the positions.
+pub fn validate(mesh: &Mesh) -> Result<(), TriMeshBuildError> {
+    can_convert(mesh)?;
+    let expected = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .map_or(0, vertex_count);
+
+    for (attribute, values) in mesh.attributes() {
+        let len = values.len();
+        if len != expected {
+            return Err(TriMeshBuildError::InconsistentAttributeLengths {
+                attribute,
+                len,
+                expected,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Find the pairs of the [`Mesh`] triangles that intersect each other.
+///
+/// The neighbouring triangles, that share an edge, are not tested, as they
+/// always touch. The triangles sharing a single vertex are only reported if
+/// they also intersect away from it. The verticies are compared by their
+/// positions, up to a millionth of the mesh size, so this works for
+/// the meshes with the duplicated verticies too. The triangles only touching
+/// each other are reported as well.
+///
+/// The pairs are listed in order, with the smaller triangle index first.
+pub fn self_intersections(mesh: &Mesh) -> Result<Vec<(u32, u32)>, TriMeshBuildError> {
+    let (verticies, indicies) = collect_geometry(mesh)?;
+    let triangles: Vec<_> = indicies
+        .iter()
+        .map(|triangle| triangle.map(|index| verticies[index as usize]))
+        .collect();
+
+    let mut qbvh = Qbvh::new();
+    let leaves = triangles
+        .iter()
+        .enumerate()
+        .map(|(index, &[a, b, c])| (index as u32, Triangle::new(a, b, c).compute_local_aabb()));
+    qbvh.clear_and_rebuild(leaves, 0.0);
+
+    let size = qbvh.root_aabb().extents().norm();
+    let tolerance = size * 1e-6;
+    let identity = Isometry::identity();
+    let mut pairs = Vec::new();
+    let mut candidates = Vec::new();
+    for (index, &[a, b, c]) in triangles.iter().enumerate() {
+        let triangle = Triangle::new(a, b, c);
+        candidates.clear();
+        qbvh.intersect_aabb(&triangle.compute_local_aabb(), &mut candidates);
+        candidates.sort_unstable();
+        for &other in candidates.iter().filter(|&&other| other as usize > index) {
+            let other_verticies = triangles[other as usize];
+            let [d, e, f] = other_verticies;
+            let other_triangle = Triangle::new(d, e, f);
+            let mut shared = (0..3)
+                .flat_map(|i| (0..3).map(move |j| (i, j)))
+                .filter(|&(i, j)| {
+                    na::distance(&triangle.vertices()[i], &other_verticies[j]) <= tolerance
+                });
+            // The convex shapes are always supported.
+            let intersects = match (shared.next(), shared.next()) {
+                (None, _) => intersection_test(&identity, &triangle, &identity, &other_triangle)
+                    .unwrap_or(false),
+                // With a single shared vertex, the triangles intersect away
+                // from it if one of them crosses the opposite edge of
+                // the other.
+                (Some((i, j)), None) => {
+                    let [_, first, second] = opposite_edge(triangle.vertices(), i);
+                    let [_, other_first, other_second] = opposite_edge(&other_verticies, j);
+                    let edge = Segment::new(first, second);
+                    let other_edge = Segment::new(other_first, other_second);
+                    intersection_test(&identity, &edge, &identity, &other_triangle).unwrap_or(false)
+                        || intersection_test(&identity, &other_edge, &identity, &triangle)
+                            .unwrap_or(false)
+                }
+                (Some(_), Some(_)) => false,
+            };
+            if intersects {
+                pairs.push((index as u32, other));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// The triangle verticies, starting with the one at `vertex`, followed by
+/// the edge opposite to it.
+fn opposite_edge(verticies: &[Point<Real>; 3], vertex: usize) -> [Point<Real>; 3] {
+    [0, 1, 2].map(|offset| verticies[(vertex + offset) % 3])
+}
+
+impl CachedTriMeshBuilder {
+    /// Check that all the indicies refer to the existing verticies.
+    ///
+    /// The geometry processing methods keep this invariant, and assert it in
+    /// the debug builds, so this only fails if the fields were modified
+    /// directly.
+    pub fn validate_invariant(&self) -> Result<(), TriMeshBuildError> {
+        let len = self.verticies.len();
+        match self.indicies.iter().flatten().max() {
+            Some(&index) if index as usize >= len => Err(TriMeshBuildError::IndexOutOfBounds {
+                index: index as usize,
+                len,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{mesh::Indices, render_resource::PrimitiveTopology};
+
+    use super::*;
+
+    fn triangles(verticies: Vec<[f32; 3]>) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        let indicies = (0..verticies.len() as u32).collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, verticies);
+        mesh.set_indices(Some(Indices::U32(indicies)));
+        mesh
+    }
+
+    #[test]
+    fn crossing_triangles_intersect() {
+        let mesh = triangles(vec![
+            [-1.0, 0.0, -1.0],
+            [1.0, 0.0, -1.0],
+            [0.0, 0.0, 1.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 1.0, 2.0],
+            [5.0, 5.0, 5.0],
+            [6.0, 5.0, 5.0],
+            [5.0, 6.0, 5.0],
+        ]);
+        assert_eq!(self_intersections(&mesh), Ok(vec![(0, 1)]));
+    }
+
+    #[test]
+    fn piercing_triangle_sharing_a_vertex_intersects() {
+        let mesh = triangles(vec![
+            [0.0, 0.0, 0.0],
+            [2.0, 0.0, -1.0],
+            [2.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0],
+            [2.0, -1.0, 0.0],
+            [2.0, 1.0, 0.0],
+        ]);
+        assert_eq!(self_intersections(&mesh), Ok(vec![(0, 1)]));
+    }
+
+    #[test]
+    fn closed_meshes_do_not_intersect() {
+        let cube = Mesh::from(shape::Cube { size: 1.0 });
+        assert_eq!(self_intersections(&cube), Ok(vec![]));
+        let sphere = Mesh::try_from(shape::Icosphere {
+            radius: 1.0,
+            subdivisions: 3,
+        })
+        .unwrap();
+        assert_eq!(self_intersections(&sphere), Ok(vec![]));
+        let torus = Mesh::from(shape::Torus::default());
+        assert_eq!(self_intersections(&torus), Ok(vec![]));
+    }
+
+    #[test]
+    fn reports_the_mismatched_attribute_lengths() {
+        let mut cube = Mesh::from(shape::Cube::new(2.0));
+        assert_eq!(validate(&cube), Ok(()));
+        cube.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; 20]);
+        assert_eq!(
+            validate(&cube),
+            Err(TriMeshBuildError::InconsistentAttributeLengths {
+                attribute: Mesh::ATTRIBUTE_NORMAL.id,
+                len: 20,
+                expected: 24,
+            })
+        );
+    }
+
+    #[test]
+    fn processing_keeps_the_invariant() {
+        let sphere = Mesh::from(shape::UVSphere::default());
+        let mut builder = CachedTriMeshBuilder::from_mesh(&sphere).unwrap();
+        builder.weld(1e-4);
+        assert_eq!(builder.validate_invariant(), Ok(()));
+        builder.indicies.truncate(builder.indicies.len() / 2);
+        builder.prune_unused_vertices();
+        assert_eq!(builder.validate_invariant(), Ok(()));
+        let trimesh = builder.build();
+        assert_eq!(trimesh.indices(), &builder.indicies);
+
+        let len = builder.verticies.len();
+        builder.verticies.pop();
+        assert_eq!(
+            builder.validate_invariant(),
+            Err(TriMeshBuildError::IndexOutOfBounds {
+                index: len - 1,
+                len: len - 1,
+            })
+        );
+    }
+}
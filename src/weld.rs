@@ -0,0 +1,270 @@
+//! Merging of the coincident verticies.
+
+use bevy::{prelude::*, utils::HashMap};
+use parry3d::{
+    bounding_volume::Aabb,
+    math::{Point, Real, Vector},
+};
+
+use crate::{CachedTriMeshBuilder, TriMeshBuildError};
+
+impl CachedTriMeshBuilder {
+    /// Merge the verticies that are within `distance` of each other.
+    ///
+    /// Each vertex is merged into the earliest vertex kept so far that is
+    /// within reach, so the result only depends on the vertex order.
+    /// Triangles that become degenerate are kept as is.
+    ///
+    /// Returns the new index of each of the original verticies, so that
+    /// the other per-vertex data can be carried along.
+    pub fn weld(&mut self, distance: Real) -> Vec<u32> {
+        self.weld_by(distance, |_, _| true)
+    }
+
+    /// Merge the verticies with the smallest weld distance that brings
+    /// the vertex count down to `max_verticies`.
+    ///
+    /// The distance is found with a binary search, so this welds a number of
+    /// times on a copy of the verticies before welding for real.
+    /// See [`Self::weld`] for the details of the welding.
+    ///
+    /// Fails with [`TriMeshBuildError::WeldTargetUnreachable`], leaving
+    /// the geometry intact, if the target can not be reached while keeping at
+    /// least one non-degenerate triangle.
+    pub fn weld_to_target(&mut self, max_verticies: usize) -> Result<Vec<u32>, TriMeshBuildError> {
+        /// The amount of the binary search steps, enough for the `f32`.
+        const STEPS: usize = 32;
+
+        // Weld a copy of the verticies, without the triangles to remap.
+        let trial = |distance: Real| {
+            let mut trial = Self {
+                verticies: self.verticies.clone(),
+                ..Self::default()
+            };
+            let remap = trial.weld(distance);
+            (trial.verticies.len(), remap)
+        };
+        let unreachable = TriMeshBuildError::WeldTargetUnreachable {
+            target: max_verticies,
+        };
+
+        let (mut count, mut remap) = trial(0.0);
+        let mut distance = 0.0;
+        if count > max_verticies {
+            let aabb = Aabb::from_points(&self.verticies);
+            let (mut low, mut high) = (0.0, aabb.extents().norm());
+            (count, remap) = trial(high);
+            if count > max_verticies {
+                return Err(unreachable);
+            }
+            distance = high;
+            for _ in 0..STEPS {
+                let middle = (low + high) / 2.0;
+                let (middle_count, middle_remap) = trial(middle);
+                if middle_count <= max_verticies {
+                    (high, distance, remap) = (middle, middle, middle_remap);
+                } else {
+                    low = middle;
+                }
+            }
+        }
+
+        // Without any triangles, there are none to destroy.
+        let destroyed = !self.indicies.is_empty()
+            && self.indicies.iter().all(|triangle| {
+                let [a, b, c] = triangle.map(|index| remap[index as usize]);
+                a == b || b == c || c == a
+            });
+        if destroyed {
+            return Err(unreachable);
+        }
+        Ok(self.weld(distance))
+    }
+
+    /// Merge the verticies with exactly the same positions.
+    ///
+    /// Same as [`Self::weld`] with the zero distance.
+    pub fn dedup_exact(&mut self) -> Vec<u32> {
+        self.weld(0.0)
+    }
+
+    /// Merge the verticies that are within `distance` of each other, and
+    /// have the `normals` no more than `max_angle` radians apart.
+    ///
+    /// Keeps the hard edges of a renderable mesh, where the verticies share
+    /// a position but not a normal. See [`Self::weld`] for details.
+    pub fn weld_with_normals(
+        &mut self,
+        normals: &[[f32; 3]],
+        distance: Real,
+        max_angle: Real,
+    ) -> Result<Vec<u32>, TriMeshBuildError> {
+        let expected = self.verticies.len();
+        if normals.len() != expected {
+            return Err(TriMeshBuildError::InconsistentAttributeLengths {
+                attribute: Mesh::ATTRIBUTE_NORMAL.id,
+                len: normals.len(),
+                expected,
+            });
+        }
+
+        let min_cos = max_angle.cos();
+        let remap = self.weld_by(distance, |a, b| {
+            let [a, b] = [a, b].map(|index| Vector::from(normals[index]).normalize());
+            a.dot(&b) >= min_cos
+        });
+        Ok(remap)
+    }
+
+    /// Merge the verticies that are within `distance` of each other and are
+    /// `compatible`, as decided by their original indicies.
+    fn weld_by(&mut self, distance: Real, compatible: impl Fn(usize, usize) -> bool) -> Vec<u32> {
+        // Bucket the verticies in a grid with the cells of the weld distance
+        // size, so only the neighbouring cells have to be looked at.
+        // The zero distance only merges the exactly equal positions.
+        let cell_of = |point: &Point<Real>| -> [i64; 3] {
+            if distance > 0.0 {
+                point.coords.map(|c| (c / distance).floor() as i64).into()
+            } else {
+                point.coords.map(|c| i64::from(c.to_bits())).into()
+            }
+        };
+        let reach = if distance > 0.0 { 1 } else { 0 };
+        let neighbours: Vec<[i64; 3]> = (-reach..=reach)
+            .flat_map(|x| {
+                (-reach..=reach).flat_map(move |y| (-reach..=reach).map(move |z| [x, y, z]))
+            })
+            .collect();
+
+        // Only used for lookups, to keep the result deterministic.
+        let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::default();
+        let mut welded: Vec<Point<Real>> = Vec::with_capacity(self.verticies.len());
+        let mut originals: Vec<usize> = Vec::with_capacity(self.verticies.len());
+        let mut remap = Vec::with_capacity(self.verticies.len());
+
+        for (index, point) in self.verticies.iter().enumerate() {
+            let cell = cell_of(point);
+            let mut found: Option<u32> = None;
+            for offset in &neighbours {
+                let neighbour = [0, 1, 2].map(|axis| cell[axis].wrapping_add(offset[axis]));
+                let Some(candidates) = grid.get(&neighbour) else {
+                    continue;
+                };
+                // The candidates are sorted, so only the first match counts.
+                let candidate = candidates.iter().copied().find(|&candidate| {
+                    let candidate = candidate as usize;
+                    parry3d::na::distance(&welded[candidate], point) <= distance
+                        && compatible(originals[candidate], index)
+                });
+                if let Some(candidate) = candidate {
+                    found = Some(found.map_or(candidate, |found| found.min(candidate)));
+                }
+            }
+
+            let new_index = found.unwrap_or_else(|| {
+                let new_index = welded.len() as u32;
+                welded.push(*point);
+                originals.push(index);
+                grid.entry(cell).or_default().push(new_index);
+                new_index
+            });
+            remap.push(new_index);
+        }
+
+        self.verticies = welded;
+        for triangle in &mut self.indicies {
+            *triangle = triangle.map(|index| remap[index as usize]);
+        }
+        debug_assert_eq!(self.validate_invariant(), Ok(()));
+        self.mark_changed();
+        remap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+
+    use super::*;
+
+    #[test]
+    fn welds_a_high_poly_sphere_down_to_the_target() {
+        let sphere = Mesh::from(shape::UVSphere {
+            radius: 1.0,
+            sectors: 64,
+            stacks: 64,
+        });
+        let mut builder = CachedTriMeshBuilder::from_mesh(&sphere).unwrap();
+        assert!(builder.verticies.len() > 4000);
+
+        builder.weld_to_target(500).unwrap();
+        assert!(builder.verticies.len() <= 500);
+        builder.remove_degenerate_triangles();
+        assert!(!builder.indicies.is_empty());
+        assert_eq!(builder.validate_invariant(), Ok(()));
+    }
+
+    #[test]
+    fn empty_geometry_already_meets_the_target() {
+        let mut builder = CachedTriMeshBuilder::new();
+        assert_eq!(builder.weld_to_target(10), Ok(vec![]));
+    }
+
+    #[test]
+    fn unreachable_target_leaves_the_geometry_intact() {
+        let cube = Mesh::from(shape::Cube { size: 1.0 });
+        let mut builder = CachedTriMeshBuilder::from_mesh(&cube).unwrap();
+        assert_eq!(
+            builder.weld_to_target(1),
+            Err(TriMeshBuildError::WeldTargetUnreachable { target: 1 })
+        );
+        assert_eq!(builder.verticies.len(), 24);
+    }
+
+    #[test]
+    fn keeps_the_hard_edges_of_a_box_apart() {
+        let cube = Mesh::from(shape::Cube::new(2.0));
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            cube.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            unreachable!();
+        };
+
+        let mut builder = CachedTriMeshBuilder::from_mesh(&cube).unwrap();
+        builder.weld_with_normals(normals, 1e-4, 0.1).unwrap();
+        assert_eq!(builder.verticies.len(), 24);
+
+        // Smoothing across the right angle merges all the face corners.
+        builder.weld_with_normals(normals, 1e-4, 2.0).unwrap();
+        assert_eq!(builder.verticies.len(), 8);
+    }
+
+    #[test]
+    fn rejects_the_normals_not_matching_the_verticies() {
+        let mut builder = CachedTriMeshBuilder::from_mesh(&shape::Cube::new(2.0).into()).unwrap();
+        assert_eq!(
+            builder.weld_with_normals(&[[0.0, 1.0, 0.0]], 1e-4, 0.1),
+            Err(TriMeshBuildError::InconsistentAttributeLengths {
+                attribute: Mesh::ATTRIBUTE_NORMAL.id,
+                len: 1,
+                expected: 24,
+            })
+        );
+    }
+
+    #[test]
+    fn welding_is_deterministic() {
+        let torus = Mesh::from(shape::Torus::default());
+        let process = || {
+            let mut builder = CachedTriMeshBuilder::from_mesh(&torus).unwrap();
+            let remap = builder.weld(1e-3);
+            builder.normalize_winding(true);
+            let trimesh = builder.build();
+            (remap, trimesh.vertices().clone(), trimesh.indices().clone())
+        };
+        let first = process();
+        for _ in 0..4 {
+            assert_eq!(process(), first);
+        }
+    }
+}